@@ -1,19 +1,57 @@
 pub mod node {
+    use std::fmt;
+    use num_bigint::BigInt;
+    use crate::error::error::Span;
     use super::ast::NodeKey;
 
 
     #[derive(Debug, Clone, PartialEq)]
     pub enum Node {
         BinaryExpr(BinaryExpr),
+        UnaryExpr(UnaryExpr),
+
+        // An expression wrapped in parentheses
+        Grouping(NodeKey),
 
         // Literals
         StringLitExpr(LiteralExpr),
         NumberLitExpr(LiteralExpr),
         IntegerLitExpr(LiteralExpr),
+        BooleanLitExpr(LiteralExpr),
+
+        // An interpolated string, made by joining each part as a string
+        Concat(Vec<NodeKey>),
+
+        // Explicit conversion like `integer(3.9)`
+        Cast(Cast),
+
+        // Variables
+        Ident(String),
+        VarDecl(VarDecl),
+        Assign(Assign),
+
+        // Procedures
+        ProcDecl(ProcDecl),
+        Call(Call),
+        Return(Option<NodeKey>),
+
+        // `print` or `cout` followed by comma separated values
+        Print(Vec<NodeKey>),
+
+        // Selection
+        If(If),
+
+        // Iteration
+        While(While),
+        For(For),
+        Break,
+        Continue,
+
+        // Statements run in their own scope
+        Block(Vec<NodeKey>),
 
-        // Other
-        RootNode,
-        EndNode,
+        // Every top level statement in the file, in order
+        Program(Vec<NodeKey>),
     }
 
     #[derive(Debug, Clone, PartialEq)]
@@ -23,13 +61,35 @@ pub mod node {
         Multiply,
         Divide,
         Modulus,
+        Power,
+
+        // Comparison
+        Equal,
+        NotEqual,
+        Less,
+        LessEqual,
+        Greater,
+        GreaterEqual,
+
+        // Logical
+        And,
+        Or,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum UnaryOp {
+        Negate,
+        Not,
     }
 
     #[derive(Debug, Clone, PartialEq)]
     pub enum LiteralType {
         String(String),
         Number(f64),
-        Integer(i32),
+        // Integers too large for 64 bits are kept as bignums
+        Integer(i64),
+        BigInteger(BigInt),
+        Boolean(bool),
     }
 
     #[derive(Debug, Clone, PartialEq)]
@@ -39,18 +99,181 @@ pub mod node {
         pub rn: NodeKey,
     }   
 
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct UnaryExpr {
+        pub op: UnaryOp,
+        pub rn: NodeKey,
+    }
+
     #[derive(Debug, Clone, PartialEq)]
     pub struct LiteralExpr {
         pub typ: LiteralType,
     }
+
+    // Types that can be written in an annotation like `x: integer = 5`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Type {
+        Integer,
+        Number,
+        String,
+        Boolean,
+        Procedure,
+    }
+
+    // `<name>: <type> = <value>` where the annotation is optional
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct VarDecl {
+        pub name: String,
+        pub name_span: Span,
+        pub typ: Option<Type>,
+        pub value: NodeKey,
+    }
+
+    // `<name> -> <value>` rebinding an already declared variable
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Assign {
+        pub name: String,
+        pub name_span: Span,
+        pub value: NodeKey,
+    }
+
+    // A typed procedure parameter like `num: number`
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Param {
+        pub name: String,
+        pub typ: Type,
+    }
+
+    // `proc <name> (<params>) returns <type> { <body> }` where the
+    // return type is optional
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ProcDecl {
+        pub name: String,
+        pub name_span: Span,
+        pub params: Vec<Param>,
+        pub returns: Option<Type>,
+        pub body: NodeKey,
+    }
+
+    // `<callee>(<args>)`
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Call {
+        pub callee: NodeKey,
+        pub args: Vec<NodeKey>,
+    }
+
+    // `<type>(<value>)` like `integer(3.9)`
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Cast {
+        pub typ: Type,
+        pub value: NodeKey,
+    }
+
+    // `if <cond> { <then> } else <otherwise>` where otherwise is either
+    // a block or another if for `else if`
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct If {
+        pub cond: NodeKey,
+        pub then: NodeKey,
+        pub otherwise: Option<NodeKey>,
+    }
+
+    // `while <cond> { <body> }`
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct While {
+        pub cond: NodeKey,
+        pub body: NodeKey,
+    }
+
+    // `for <var> in <start>..<end> { <body> }` where `end` is exclusive
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct For {
+        pub var: String,
+        pub var_span: Span,
+        pub start: NodeKey,
+        pub end: NodeKey,
+        pub body: NodeKey,
+    }
+
+    impl Node {
+        // Keys of the nodes directly beneath this one, in source order
+        pub fn children(&self) -> Vec<NodeKey> {
+            match self {
+                Node::BinaryExpr(expr) => vec![expr.ln, expr.rn],
+                Node::UnaryExpr(expr) => vec![expr.rn],
+                Node::Grouping(inner) => vec![*inner],
+                Node::Cast(cast) => vec![cast.value],
+                Node::VarDecl(decl) => vec![decl.value],
+                Node::Assign(assign) => vec![assign.value],
+                Node::ProcDecl(decl) => vec![decl.body],
+                Node::Call(call) => std::iter::once(call.callee).chain(call.args.iter().copied()).collect(),
+                Node::Return(value) => value.iter().copied().collect(),
+                Node::If(stmt) => [stmt.cond, stmt.then].into_iter().chain(stmt.otherwise).collect(),
+                Node::While(stmt) => vec![stmt.cond, stmt.body],
+                Node::For(stmt) => vec![stmt.start, stmt.end, stmt.body],
+                Node::Concat(keys) | Node::Print(keys) | Node::Block(keys) | Node::Program(keys) => keys.clone(),
+                Node::StringLitExpr(_) | Node::NumberLitExpr(_) | Node::IntegerLitExpr(_) | Node::BooleanLitExpr(_)
+                | Node::Ident(_) | Node::Break | Node::Continue => Vec::new(),
+            }
+        }
+    }
+
+    impl fmt::Display for BinaryOp {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let symbol = match self {
+                BinaryOp::Plus => "+",
+                BinaryOp::Minus => "-",
+                BinaryOp::Multiply => "*",
+                BinaryOp::Divide => "/",
+                BinaryOp::Modulus => "%",
+                BinaryOp::Power => "^",
+                BinaryOp::Equal => "==",
+                BinaryOp::NotEqual => "!=",
+                BinaryOp::Less => "<",
+                BinaryOp::LessEqual => "<=",
+                BinaryOp::Greater => ">",
+                BinaryOp::GreaterEqual => ">=",
+                BinaryOp::And => "and",
+                BinaryOp::Or => "or",
+            };
+            write!(f, "{}", symbol)
+        }
+    }
+
+    impl Type {
+        // Converts the lexeme of a type token into a type
+        pub fn from_name(name: &str) -> Option<Type> {
+            match name {
+                "integer" => Some(Type::Integer),
+                "number" => Some(Type::Number),
+                "string" => Some(Type::String),
+                "boolean" => Some(Type::Boolean),
+                _ => None,
+            }
+        }
+    }
+
+    impl fmt::Display for Type {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Type::Integer => write!(f, "integer"),
+                Type::Number => write!(f, "number"),
+                Type::String => write!(f, "string"),
+                Type::Boolean => write!(f, "boolean"),
+                Type::Procedure => write!(f, "procedure"),
+            }
+        }
+    }
 }
 
 pub mod ast {
     use std::{iter::Peekable, vec::IntoIter};
 
-    use slotmap::{new_key_type, SlotMap};
+    use num_bigint::BigInt;
+    use slotmap::{new_key_type, SecondaryMap, SlotMap};
     use crate::token::token::{TokenType, Token};
-    use super::node::{LiteralExpr, LiteralType, Node};
+    use crate::error::error::{self, Diagnostic, Span};
+    use super::node::{BinaryExpr, BinaryOp, Assign, Call, Cast, For, If, LiteralExpr, LiteralType, Node, Param, ProcDecl, Type, UnaryExpr, UnaryOp, VarDecl, While};
 
     // Define custom result type
     enum AstRes<Node> {
@@ -67,117 +290,849 @@ pub mod ast {
     pub struct Ast {
         pub stream: Peekable<IntoIter<Token>>,
         pub tree: SlotMap<NodeKey, Node>,
+        pub spans: SecondaryMap<NodeKey, Span>,
         pub root: NodeKey,
         pub current: Token,
-        pub keys: Vec<NodeKey>,
+
+        // How many procedure bodies and loops the parser is currently inside
+        proc_depth: usize,
+        loop_depth: usize,
+
+        // How many parentheses are open, newlines inside them are skipped
+        // `line_start` is set when current is the first token after a skipped newline
+        paren_depth: usize,
+        line_start: bool,
+
+        // Errors collected while parsing
+        diagnostics: Vec<Diagnostic>,
     }
 
     impl Ast {
-        pub fn new(tokens: Vec<Token>) -> Self {
+        pub fn new(tokens: Vec<Token>) -> Result<Self, Diagnostic> {
             let mut tree: SlotMap<NodeKey, Node> = SlotMap::with_key();
-            let keys = Vec::<NodeKey>::new();
-
-            // Construct iterator from tokens
-            let mut stream = tokens.into_iter().peekable();
-            let current = match stream.next() {
-                Some(current) => current,
-                None => {
-                    eprintln!("Recieved an empty token stream!");
-                    std::process::exit(1);
-                }
-            };
+            let spans: SecondaryMap<NodeKey, Span> = SecondaryMap::new();
+            let (stream, current) = Self::load(tokens)?;
 
-            // Create root node and construct
-            let root = tree.insert(Node::RootNode);
-            Self { stream, tree, root, current, keys }
+            // Create the program node at the root and construct
+            let root = tree.insert(Node::Program(Vec::new()));
+            Ok(Self {
+                stream, tree, spans, root, current,
+                proc_depth: 0, loop_depth: 0, paren_depth: 0, line_start: false,
+                diagnostics: Vec::new(),
+            })
+        }
+
+        // Construct iterator from tokens, comments are never parsed
+        fn load(tokens: Vec<Token>) -> Result<(Peekable<IntoIter<Token>>, Token), Diagnostic> {
+            let mut stream = tokens
+                .into_iter()
+                .filter(|t| t.token_type != TokenType::Comment)
+                .collect::<Vec<Token>>()
+                .into_iter()
+                .peekable();
+            match stream.next() {
+                Some(current) => Ok((stream, current)),
+                None => Err(Diagnostic::error(
+                    error::EMPTY_STREAM,
+                    String::from("Recieved an empty token stream"),
+                    Span::line(1),
+                )),
+            }
+        }
+
+        // Parses another stream of tokens into the same tree, returning the
+        // keys of it's statements rather than adding them to the program
+        // This lets the REPL run each entry on it's own while procedures
+        // declared in earlier entries, whose bodies live in the tree, can
+        // still be called
+        pub fn parse_more(&mut self, tokens: Vec<Token>) -> Result<Vec<NodeKey>, Vec<Diagnostic>> {
+            let (stream, current) = Self::load(tokens).map_err(|e| vec![e])?;
+            self.stream = stream;
+            self.current = current;
+            self.paren_depth = 0;
+            self.line_start = false;
+
+            let body = self.parse_statements(TokenType::EndFile);
+            if self.diagnostics.is_empty() {
+                Ok(body)
+            } else {
+                Err(std::mem::take(&mut self.diagnostics))
+            }
         }
 
         // Takes the stream of incoming tokens and constructs an
         // abstract syntax tree based on it
-        pub fn parse(&mut self) {
-            'parse: loop {
-                let node = match self.match_token() {
-                    AstRes::Match(n) => {
-                        self.push_node(n);
-                    },
-                    AstRes::None => {
-                        
+        // Every top level statement is owned by the program node at the root
+        // Errors are collected and the parser skips to the next line
+        // so that every error in the file can be reported at once
+        pub fn parse(&mut self) -> Result<(), Vec<Diagnostic>> {
+            let body = self.parse_statements(TokenType::EndFile);
+            self.tree[self.root] = Node::Program(body);
+
+            if self.diagnostics.is_empty() {
+                Ok(())
+            } else {
+                Err(std::mem::take(&mut self.diagnostics))
+            }
+        }
+
+        // Lexes and parses a whole program, panicking if there are any errors
+        // Shared by the tests of each stage that needs a tree to work on
+        #[cfg(test)]
+        pub fn from_source(source: &str) -> Self {
+            let mut lexer = crate::lexer::lexer::Lexer::new(source.chars().peekable(), Vec::new());
+            lexer.scan().expect("source should lex");
+            let mut ast = Ast::new(lexer.tokens).expect("token stream should end with EOF");
+            ast.parse().expect("source should parse");
+            ast
+        }
+
+        // Parses newline separated statements until `end` (or EOF) is reached,
+        // leaving current on it
+        // Each statement must be followed by a newline, or be the last one
+        // before `end`, so `x = 1 y = 2` is an error
+        fn parse_statements(&mut self, end: TokenType) -> Vec<NodeKey> {
+            let mut body = Vec::<NodeKey>::new();
+
+            'statements: loop {
+                match self.current.token_type {
+                    // Blank lines between statements are skipped
+                    TokenType::NewLn => {
+                        if !self.advance() {
+                            break 'statements;
+                        }
                     },
-                    AstRes::End => {
-                        self.push_node(Node::EndNode);
-                        break 'parse;
+
+                    TokenType::EndFile => break 'statements,
+                    ref token_type if *token_type == end => break 'statements,
+
+                    _ => match self.parse_statement().and_then(|key| self.expect_terminator(&end).map(|_| key)) {
+                        Ok(key) => body.push(key),
+                        Err(diagnostic) => {
+                            // If an unclosed `(` ran onto the next line the error is
+                            // reported at the `(`, so parsing resumes on the new line
+                            // rather than skipping it
+                            let resume = self.line_start && diagnostic.span.line < self.current.span.line;
+                            self.diagnostics.push(diagnostic);
+                            self.synchronize(&end, resume);
+                        },
                     },
+                }
+            }
+
+            body
+        }
+
+        // Checks that a statement is followed by a newline, EOF or `end`
+        fn expect_terminator(&self, end: &TokenType) -> Result<(), Diagnostic> {
+            match self.current.token_type {
+                TokenType::NewLn | TokenType::EndFile => Ok(()),
+                ref token_type if token_type == end => Ok(()),
+                TokenType::RParen => Err(self.error(
+                    error::UNMATCHED_PAREN,
+                    String::from("Unmatched `)` with no `(` to close"),
+                )),
+                _ => Err(self.error(
+                    error::EXPECTED_NEWLINE,
+                    format!("Expected a newline after the statement but found `{}`", self.current.lexeme),
+                )),
+            }
+        }
+
+        // Skips tokens until the start of the next line, `end` or EOF so
+        // parsing can resume after an error, unless `resume` is set in which
+        // case parsing resumes at current
+        fn synchronize(&mut self, end: &TokenType, resume: bool) {
+            self.paren_depth = 0;
+            self.line_start = false;
+            if resume {
+                return;
+            }
+
+            while !matches!(self.current.token_type, TokenType::NewLn | TokenType::EndFile) && self.current.token_type != *end {
+                if !self.advance() {
+                    break;
+                }
+            }
+        }
+
+        // Parses a single statement, which is either a block, a procedure,
+        // a return, a print, an if, a loop, a declaration, a reassignment
+        // or an expression
+        fn parse_statement(&mut self) -> Result<NodeKey, Diagnostic> {
+            match self.current.token_type {
+                TokenType::LBrace => return self.parse_block(),
+                TokenType::Proc => return self.parse_proc(),
+                TokenType::Return => return self.parse_return(),
+                TokenType::Cout => return self.parse_print(),
+                TokenType::If => return self.parse_if(),
+                TokenType::Else => return self.parse_dangling_else(),
+                TokenType::While => return self.parse_while(),
+                TokenType::For => return self.parse_for(),
+                TokenType::Break | TokenType::Continue => return self.parse_loop_control(),
+                _ => {},
+            }
+
+            if self.current.token_type == TokenType::Ident {
+                if let Some(next) = self.stream.peek() {
+                    match next.token_type {
+                        TokenType::Colon | TokenType::Equals => return self.parse_var_decl(),
+                        TokenType::Arrow => return self.parse_assign(),
+                        _ => {},
+                    }
+                }
+            }
+
+            self.parse_expr(0)
+        }
+
+        // Parses `<name>: <type> = <value>` or `<name> = <value>`
+        fn parse_var_decl(&mut self) -> Result<NodeKey, Diagnostic> {
+            let name = self.expect(TokenType::Ident, "a variable name")?;
+
+            // Optional type annotation
+            let mut typ = None;
+            if self.current.token_type == TokenType::Colon {
+                self.advance();
+                typ = Some(self.parse_type()?);
+            }
+
+            self.expect(TokenType::Equals, "`=`")?;
+            let value = self.parse_expr(0)?;
+
+            let span = name.span.to(self.span(value));
+            Ok(self.push_node(Node::VarDecl(VarDecl { name: name.lexeme, name_span: name.span, typ, value }), span))
+        }
+
+        // Parses `{ <statements> }`, where statements are separated by newlines
+        // Errors inside the block are recorded and parsing carries on up to the
+        // closing `}`, so only a block that is never closed fails as a whole
+        fn parse_block(&mut self) -> Result<NodeKey, Diagnostic> {
+            let open = self.expect(TokenType::LBrace, "`{`")?;
+            let body = self.parse_statements(TokenType::RBrace);
+
+            if self.current.token_type == TokenType::EndFile {
+                return Err(Diagnostic::error(
+                    error::EXPECTED_TOKEN,
+                    String::from("Block is never closed, expected `}`"),
+                    open.span,
+                ));
+            }
+
+            let close = self.expect(TokenType::RBrace, "`}`")?;
+            Ok(self.push_node(Node::Block(body), open.span.to(close.span)))
+        }
+
+        // Parses `proc <name> (<name>: <type>, ...) returns <type> { <body> }`
+        fn parse_proc(&mut self) -> Result<NodeKey, Diagnostic> {
+            let proc_token = self.expect(TokenType::Proc, "`proc`")?;
+            let name = self.expect(TokenType::Ident, "a procedure name")?;
+
+            // Parameter list
+            // A repeated name is reported without stopping, since the rest of
+            // the procedure can still be parsed
+            let mut seen = Vec::<String>::new();
+            let (params, _) = self.parse_list(|ast| {
+                let param = ast.expect(TokenType::Ident, "a parameter name")?;
+                if seen.contains(&param.lexeme) {
+                    ast.diagnostics.push(Diagnostic::error(
+                        error::DUPLICATE_PARAM,
+                        format!("`{}` has more than one parameter called `{}`", name.lexeme, param.lexeme),
+                        param.span,
+                    ));
+                }
+                seen.push(param.lexeme.clone());
+
+                ast.expect(TokenType::Colon, "`:` and a parameter type")?;
+                let typ = ast.parse_type()?;
+                Ok(Param { name: param.lexeme, typ })
+            })?;
+
+            // Optional return type
+            let mut returns = None;
+            if self.current.token_type == TokenType::Returns {
+                self.advance();
+                returns = Some(self.parse_type()?);
+            }
+
+            // Loops outside the procedure can't be broken out of from inside it
+            let loop_depth = std::mem::take(&mut self.loop_depth);
+            self.proc_depth += 1;
+            let body = self.parse_block();
+            self.proc_depth -= 1;
+            self.loop_depth = loop_depth;
+            let body = body?;
+
+            let span = proc_token.span.to(self.span(body));
+            let decl = ProcDecl { name: name.lexeme, name_span: name.span, params, returns, body };
+            Ok(self.push_node(Node::ProcDecl(decl), span))
+        }
+
+        // Parses `return` with an optional value
+        fn parse_return(&mut self) -> Result<NodeKey, Diagnostic> {
+            if self.proc_depth == 0 {
+                return Err(self.error(
+                    error::RETURN_OUTSIDE_PROC,
+                    String::from("`return` can only be used inside a procedure"),
+                ));
+            }
+
+            let return_token = self.expect(TokenType::Return, "`return`")?;
+            if matches!(self.current.token_type, TokenType::NewLn | TokenType::RBrace | TokenType::EndFile) {
+                return Ok(self.push_node(Node::Return(None), return_token.span));
+            }
+
+            let value = self.parse_expr(0)?;
+            let span = return_token.span.to(self.span(value));
+            Ok(self.push_node(Node::Return(Some(value)), span))
+        }
+
+        // Parses `print <value>, <value>, ...` where the values are optional
+        fn parse_print(&mut self) -> Result<NodeKey, Diagnostic> {
+            let print_token = self.expect(TokenType::Cout, "`print`")?;
+            let mut span = print_token.span;
+
+            // A bare `print` prints an empty line, but every `,` must be
+            // followed by another value
+            let mut values = Vec::<NodeKey>::new();
+            if matches!(self.current.token_type, TokenType::NewLn | TokenType::RBrace | TokenType::EndFile) {
+                return Ok(self.push_node(Node::Print(values), span));
+            }
+
+            'values: loop {
+                let value = self.parse_expr(0)?;
+                span = span.to(self.span(value));
+                values.push(value);
+
+                if self.current.token_type != TokenType::Comma {
+                    break 'values;
+                }
+                self.advance();
+            }
+
+            Ok(self.push_node(Node::Print(values), span))
+        }
+
+        // Parses `if <cond> { ... }` followed by any number of `else if <cond> { ... }`
+        // and an optional `else { ... }`, which must be on the same line as the `}`
+        fn parse_if(&mut self) -> Result<NodeKey, Diagnostic> {
+            let if_token = self.expect(TokenType::If, "`if`")?;
+            let cond = self.parse_expr(0)?;
+            let then = self.parse_block()?;
+
+            let mut otherwise = None;
+            if self.current.token_type == TokenType::Else {
+                self.advance();
+                otherwise = Some(match self.current.token_type {
+                    TokenType::If => self.parse_if()?,
+                    _ => self.parse_block()?,
+                });
+            }
+
+            let span = if_token.span.to(self.span(otherwise.unwrap_or(then)));
+            Ok(self.push_node(Node::If(If { cond, then, otherwise }), span))
+        }
+
+        // Reports an `else` at the start of a line, which is almost always
+        // meant for the `if` whose `}` ends the line before
+        // The branch is still parsed so it's closing `}` isn't reported as well
+        fn parse_dangling_else(&mut self) -> Result<NodeKey, Diagnostic> {
+            let else_token = self.expect(TokenType::Else, "`else`")?;
+            let diagnostic = Diagnostic::error(
+                error::DANGLING_ELSE,
+                String::from("`else` must be on the same line as the `}` that closes the `if`, as in `} else {`"),
+                else_token.span,
+            );
+
+            let branch = match self.current.token_type {
+                TokenType::If => self.parse_if(),
+                _ => self.parse_block(),
+            };
+            if let Err(branch_error) = branch {
+                self.diagnostics.push(diagnostic);
+                return Err(branch_error);
+            }
+            Err(diagnostic)
+        }
+
+        // Parses `while <cond> { <body> }`
+        fn parse_while(&mut self) -> Result<NodeKey, Diagnostic> {
+            let while_token = self.expect(TokenType::While, "`while`")?;
+            let cond = self.parse_expr(0)?;
+            let body = self.parse_loop_body()?;
+
+            let span = while_token.span.to(self.span(body));
+            Ok(self.push_node(Node::While(While { cond, body }), span))
+        }
+
+        // Parses `for <var> in <start>..<end> { <body> }`
+        fn parse_for(&mut self) -> Result<NodeKey, Diagnostic> {
+            let for_token = self.expect(TokenType::For, "`for`")?;
+            let var = self.expect(TokenType::Ident, "a loop variable name")?;
+            self.expect(TokenType::In, "`in`")?;
+            let start = self.parse_expr(0)?;
+            self.expect(TokenType::DotDot, "`..` in a range like `1..10`")?;
+            let end = self.parse_expr(0)?;
+            let body = self.parse_loop_body()?;
+
+            let span = for_token.span.to(self.span(body));
+            let stmt = For { var: var.lexeme, var_span: var.span, start, end, body };
+            Ok(self.push_node(Node::For(stmt), span))
+        }
+
+        // Parses the block of a loop, inside which `break` and `continue` are allowed
+        fn parse_loop_body(&mut self) -> Result<NodeKey, Diagnostic> {
+            self.loop_depth += 1;
+            let body = self.parse_block();
+            self.loop_depth -= 1;
+            body
+        }
+
+        // Parses `break` or `continue`
+        fn parse_loop_control(&mut self) -> Result<NodeKey, Diagnostic> {
+            if self.loop_depth == 0 {
+                return Err(self.error(
+                    error::CONTROL_OUTSIDE_LOOP,
+                    format!("`{}` can only be used inside a loop", self.current.lexeme),
+                ));
+            }
+
+            let node = match self.current.token_type {
+                TokenType::Break => Node::Break,
+                _ => Node::Continue,
+            };
+            let key = self.push_node(node, self.current.span);
+            self.advance();
+            Ok(key)
+        }
+
+        // Parses a type name
+        fn parse_type(&mut self) -> Result<Type, Diagnostic> {
+            let type_token = self.expect(TokenType::Type, "a type")?;
+            match Type::from_name(&type_token.lexeme) {
+                Some(typ) => Ok(typ),
+                None => Err(Diagnostic::error(
+                    error::EXPECTED_TOKEN,
+                    format!("Unknown type `{}`", type_token.lexeme),
+                    type_token.span,
+                )),
+            }
+        }
+
+        // Parses `<name> -> <value>`
+        fn parse_assign(&mut self) -> Result<NodeKey, Diagnostic> {
+            let name = self.expect(TokenType::Ident, "a variable name")?;
+            self.expect(TokenType::Arrow, "`->`")?;
+            let value = self.parse_expr(0)?;
+
+            let span = name.span.to(self.span(value));
+            Ok(self.push_node(Node::Assign(Assign { name: name.lexeme, name_span: name.span, value }), span))
+        }
+
+        // Consumes the current token if it is of the expected type and returns it,
+        // otherwise returns an error describing what was expected
+        fn expect(&mut self, token_type: TokenType, expected: &str) -> Result<Token, Diagnostic> {
+            if self.current.token_type != token_type {
+                return Err(self.error(
+                    error::EXPECTED_TOKEN,
+                    format!("Expected {} but found `{}`", expected, self.current.lexeme),
+                ));
+            }
+
+            let token = self.current.clone();
+            self.advance();
+            Ok(token)
+        }
+
+        // Parses an expression using precedence climbing
+        // Operands on the right of an operator are only taken while the next
+        // operator binds tighter than `min_bp`, which gives us precedence and
+        // associativity from the binding powers in `infix_op`
+        fn parse_expr(&mut self, min_bp: u8) -> Result<NodeKey, Diagnostic> {
+            let mut ln = match prefix_op(&self.current.token_type) {
+                // Prefix operators take everything binding tighter than them as their operand
+                Some((op, r_bp)) => {
+                    let op_span = self.current.span;
+                    self.advance();
+                    let rn = self.parse_expr(r_bp)?;
+                    let span = op_span.to(self.span(rn));
+                    self.push_node(Node::UnaryExpr(UnaryExpr { op, rn }), span)
+                },
+                None => self.parse_operand()?,
+            };
+
+            'infix: loop {
+                let (op, l_bp, r_bp) = match infix_op(&self.current.token_type) {
+                    Some(infix) => infix,
+                    None => break 'infix,
                 };
 
-                // Advance to next token if possible
-                if self.advance() {
-                    continue 'parse;
-                } else {
-                    break 'parse;
+                if l_bp < min_bp {
+                    break 'infix;
+                }
+
+                // Skip over the operator and parse the right hand side, which
+                // may start on the next line
+                self.advance();
+                self.skip_newlines();
+                let rn = self.parse_expr(r_bp)?;
+
+                let span = self.span(ln).to(self.span(rn));
+                ln = self.push_node(Node::BinaryExpr(BinaryExpr { op, ln, rn }), span);
+            }
+
+            Ok(ln)
+        }
+
+        // Parses a single operand (a literal, a variable or a parenthesised
+        // expression) and leaves current on the token following it
+        fn parse_operand(&mut self) -> Result<NodeKey, Diagnostic> {
+            let mut key = match self.current.token_type {
+                TokenType::LParen => self.parse_grouping()?,
+                TokenType::Type if self.stream.peek().is_some_and(|t| t.token_type == TokenType::LParen) => {
+                    self.parse_cast()?
+                },
+                TokenType::StringLit if self.stream.peek().is_some_and(|t| t.token_type == TokenType::InterpStart) => {
+                    self.parse_interpolation()?
+                },
+                _ => match self.match_token()? {
+                    AstRes::Match(node) => {
+                        let key = self.push_node(node, self.current.span);
+                        self.advance();
+                        key
+                    },
+                    AstRes::None => return Err(self.error(
+                        error::EXPECTED_EXPR,
+                        format!("Expected an expression but found `{}`", self.current.lexeme),
+                    )),
+                    AstRes::End => return Err(self.error(
+                        error::EXPECTED_EXPR,
+                        String::from("Expected an expression but reached the end of the file"),
+                    )),
+                },
+            };
+
+            // Any number of calls can follow an operand, e.g. `f(1)(2)`
+            while self.current.token_type == TokenType::LParen {
+                key = self.parse_call(key)?;
+            }
+            Ok(key)
+        }
+
+        // Parses a conversion like `number("3.5")`, which takes a single value
+        fn parse_cast(&mut self) -> Result<NodeKey, Diagnostic> {
+            let type_token = self.current.clone();
+            let typ = self.parse_type()?;
+            let (args, close) = self.parse_list(|ast| ast.parse_expr(0))?;
+
+            let span = type_token.span.to(close);
+            match args[..] {
+                [value] => Ok(self.push_node(Node::Cast(Cast { typ, value }), span)),
+                _ => Err(Diagnostic::error(
+                    error::EXPECTED_EXPR,
+                    format!("`{}()` converts exactly one value but was given {}", typ, args.len()),
+                    span,
+                )),
+            }
+        }
+
+        // Parses the segments and `{}` expressions of an interpolated string
+        // into a concatenation, dropping empty segments
+        fn parse_interpolation(&mut self) -> Result<NodeKey, Diagnostic> {
+            let start = self.current.span;
+            let mut end = start;
+            let mut parts = Vec::<NodeKey>::new();
+
+            'parts: loop {
+                if self.current.token_type == TokenType::StringLit {
+                    if !self.current.lexeme.is_empty() {
+                        let literal_expr = LiteralExpr { typ: LiteralType::String(self.current.lexeme.clone()) };
+                        parts.push(self.push_node(Node::StringLitExpr(literal_expr), self.current.span));
+                    }
+                    end = self.current.span;
+                    self.advance();
                 }
+
+                if self.current.token_type != TokenType::InterpStart {
+                    break 'parts;
+                }
+                self.advance();
+                parts.push(self.parse_expr(0)?);
+                end = self.expect(TokenType::InterpEnd, "`}` to close the interpolation")?.span;
             }
+
+            Ok(self.push_node(Node::Concat(parts), start.to(end)))
+        }
+
+        // Parses `(<expr>)`, which may be split across lines
+        fn parse_grouping(&mut self) -> Result<NodeKey, Diagnostic> {
+            self.paren_depth += 1;
+            let open = self.expect(TokenType::LParen, "`(`");
+            let inner = open.clone().and_then(|_| self.parse_expr(0));
+            self.paren_depth -= 1;
+
+            let (open, inner) = (open?, inner?);
+            let close = self.expect_close(&open)?;
+            Ok(self.push_node(Node::Grouping(inner), open.span.to(close.span)))
         }
 
-        fn push_node(&mut self, node: Node) {
-            // Push node to tree and get it's unique key
+        // Consumes the `)` matching `open`, otherwise reports the unclosed `(`
+        // since that is usually where the mistake is
+        fn expect_close(&mut self, open: &Token) -> Result<Token, Diagnostic> {
+            if self.current.token_type != TokenType::RParen {
+                let found = match self.current.token_type {
+                    TokenType::EndFile => String::from("reached the end of the file"),
+                    _ => format!("found `{}` on line {}", self.current.lexeme, self.current.span.line),
+                };
+                return Err(Diagnostic::error(
+                    error::UNMATCHED_PAREN,
+                    format!("This `(` is never closed, expected `)` but {}", found),
+                    open.span,
+                ));
+            }
+
+            let token = self.current.clone();
+            self.advance();
+            Ok(token)
+        }
+        // Parses the argument list of a call to `callee`
+        fn parse_call(&mut self, callee: NodeKey) -> Result<NodeKey, Diagnostic> {
+            let (args, close) = self.parse_list(|ast| ast.parse_expr(0))?;
+            let span = self.span(callee).to(close);
+            Ok(self.push_node(Node::Call(Call { callee, args }), span))
+        }
+
+        // Parses `(<item>, <item>, ...)` using `item` for each element and
+        // returns them along with the span of the closing `)`
+        // Newlines inside the parentheses are skipped, so lists can be split
+        // across lines
+        fn parse_list<T>(
+            &mut self,
+            mut item: impl FnMut(&mut Self) -> Result<T, Diagnostic>,
+        ) -> Result<(Vec<T>, Span), Diagnostic> {
+            self.paren_depth += 1;
+            let open = self.expect(TokenType::LParen, "`(`");
+            let items = open.clone().and_then(|_| {
+                let mut items = Vec::<T>::new();
+                'items: loop {
+                    if self.current.token_type == TokenType::RParen {
+                        break 'items;
+                    }
+
+                    items.push(item(self)?);
+
+                    if self.current.token_type != TokenType::Comma {
+                        break 'items;
+                    }
+                    self.advance();
+                }
+                Ok(items)
+            });
+
+            // The newline after `)` ends the statement, so the depth has to drop
+            // before the `)` is consumed
+            self.paren_depth -= 1;
+            let (open, items) = (open?, items?);
+            let close = self.expect_close(&open)?;
+            Ok((items, close.span))
+        }
+
+        // Builds an error diagnostic spanning the current token
+        fn error(&self, code: &'static str, message: String) -> Diagnostic {
+            Diagnostic::error(code, message, self.current.span)
+        }
+
+        fn push_node(&mut self, node: Node, span: Span) -> NodeKey {
+            // Push node to tree and record where it came from
             let key = self.tree.insert(node);
+            self.spans.insert(key, span);
+            key
+        }
 
-            // Push that key to the Keys helper vec
-            self.keys.push(key);
+        // Returns the source span of a node
+        pub fn span(&self, key: NodeKey) -> Span {
+            self.spans.get(key).copied().unwrap_or_default()
         }
 
-        fn match_token(&mut self) -> AstRes<Node> {
+        fn match_token(&mut self) -> Result<AstRes<Node>, Diagnostic> {
             match self.current.token_type {
                 // Number literals
                 TokenType::NumberLit => {
                     // Determine if literal is float or integer
+                    // The lexer has already checked the literal is well formed
                     // TODO: Impliment type hinting
+                    let lexeme = &self.current.lexeme;
+                    let radix = match lexeme.get(..2) {
+                        Some("0x") => 16,
+                        Some("0b") => 2,
+                        Some("0o") => 8,
+                        _ => 10,
+                    };
                     
-                    if self.current.lexeme.contains(".") {
+                    if radix == 10 && lexeme.contains(['.', 'e', 'E']) {
                         // If float
-                        let parsed_num = match self.current.lexeme.parse::<f64>() {
-                            Ok(parsed_num) => parsed_num,
-                            Err(_) => {
-                                todo!("Error handle failure to convert string to float");
+                        let parsed_num = match lexeme.parse::<f64>() {
+                            Ok(parsed_num) if parsed_num.is_finite() => parsed_num,
+                            _ => {
+                                return Err(self.error(
+                                    error::INVALID_NUMBER,
+                                    format!("Number literal `{}` is too large", lexeme),
+                                ));
                             }
                         };
 
                         let literal_expr = LiteralExpr { typ: LiteralType::Number(parsed_num) };
-                        return AstRes::Match(Node::NumberLitExpr(literal_expr));
+                        Ok(AstRes::Match(Node::NumberLitExpr(literal_expr)))
                     } else {
-                        // If int
-                        let parsed_num = match self.current.lexeme.parse::<i32>() {
-                            Ok(parsed_num) => parsed_num,
-                            Err(_) => {
-                                todo!("Error handle failure to convert string to float");
-                            }
+                        // If int, falling back to a bignum if it doesn't fit in 64 bits
+                        let digits = if radix == 10 { lexeme.as_str() } else { &lexeme[2..] };
+                        let typ = match i64::from_str_radix(digits, radix) {
+                            Ok(parsed_num) => LiteralType::Integer(parsed_num),
+                            Err(_) => match BigInt::parse_bytes(digits.as_bytes(), radix) {
+                                Some(parsed_num) => LiteralType::BigInteger(parsed_num),
+                                None => {
+                                    return Err(self.error(
+                                        error::INVALID_NUMBER,
+                                        format!("Invalid integer literal `{}`", lexeme),
+                                    ));
+                                }
+                            },
                         };
 
-                        let literal_expr = LiteralExpr { typ: LiteralType::Integer(parsed_num) };
-                        return AstRes::Match(Node::NumberLitExpr(literal_expr));
+                        let literal_expr = LiteralExpr { typ };
+                        Ok(AstRes::Match(Node::IntegerLitExpr(literal_expr)))
                     }
                 },
 
                 // String literal
                 TokenType::StringLit => {
                     let literal_expr = LiteralExpr { typ: LiteralType::String(self.current.lexeme.to_string()) };
-                    return AstRes::Match(Node::StringLitExpr(literal_expr));
+                    Ok(AstRes::Match(Node::StringLitExpr(literal_expr)))
                 }
 
+                // Boolean literal
+                TokenType::BoolLit => {
+                    let literal_expr = LiteralExpr { typ: LiteralType::Boolean(self.current.lexeme == "true") };
+                    Ok(AstRes::Match(Node::BooleanLitExpr(literal_expr)))
+                },
+
+                // Variable reference
+                TokenType::Ident => Ok(AstRes::Match(Node::Ident(self.current.lexeme.to_string()))),
+
                 // End of file token
-                TokenType::EndFile => return AstRes::End,
+                TokenType::EndFile => Ok(AstRes::End),
 
-                _ => return AstRes::None,
+                _ => Ok(AstRes::None),
             }
         } 
 
+        // Moves to the next token, skipping newlines while inside parentheses
         fn advance(&mut self) -> bool {
-            if let Some(t) = self.stream.next() {
-                self.current = t;
-                true
-            } else {
-                false
+            let t = match self.stream.next() {
+                Some(t) => t,
+                None => return false,
+            };
+
+            self.current = t;
+            self.line_start = false;
+            while self.paren_depth > 0 && self.current.token_type == TokenType::NewLn {
+                match self.stream.next() {
+                    Some(t) => {
+                        self.current = t;
+                        self.line_start = true;
+                    },
+                    None => break,
+                }
+            }
+            true
+        }
+
+        fn skip_newlines(&mut self) {
+            while self.current.token_type == TokenType::NewLn {
+                if !self.advance() {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Returns the unary operator for a prefix token along with the binding
+    // power of it's operand
+    // `not` is looser than comparisons so `not a == b` is `not (a == b)`, while
+    // `-` and `!` are tighter than `*` but looser than `^` so `-2 ^ 2` is `-(2 ^ 2)`
+    fn prefix_op(token_type: &TokenType) -> Option<(UnaryOp, u8)> {
+        match token_type {
+            TokenType::Not => Some((UnaryOp::Not, 5)),
+            TokenType::Minus => Some((UnaryOp::Negate, 15)),
+            TokenType::Bang => Some((UnaryOp::Not, 15)),
+            _ => None,
+        }
+    }
+
+    // Returns the binary operator for a token along with it's left and right
+    // binding powers. Left associative operators bind tighter on the right,
+    // while `^` binds tighter on the left to make it right associative
+    fn infix_op(token_type: &TokenType) -> Option<(BinaryOp, u8, u8)> {
+        match token_type {
+            TokenType::Or => Some((BinaryOp::Or, 1, 2)),
+            TokenType::And => Some((BinaryOp::And, 3, 4)),
+            TokenType::EqualEqual => Some((BinaryOp::Equal, 7, 8)),
+            TokenType::BangEqual => Some((BinaryOp::NotEqual, 7, 8)),
+            TokenType::Less => Some((BinaryOp::Less, 9, 10)),
+            TokenType::LessEqual => Some((BinaryOp::LessEqual, 9, 10)),
+            TokenType::Greater => Some((BinaryOp::Greater, 9, 10)),
+            TokenType::GreaterEqual => Some((BinaryOp::GreaterEqual, 9, 10)),
+            TokenType::Plus => Some((BinaryOp::Plus, 11, 12)),
+            TokenType::Minus => Some((BinaryOp::Minus, 11, 12)),
+            TokenType::Star => Some((BinaryOp::Multiply, 13, 14)),
+            TokenType::Slash => Some((BinaryOp::Divide, 13, 14)),
+            TokenType::Percent => Some((BinaryOp::Modulus, 13, 14)),
+            TokenType::Caret => Some((BinaryOp::Power, 18, 17)),
+            _ => None,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use crate::ast::node::Node;
+        use crate::printer::printer::sexpr;
+        use super::Ast;
+
+        // Parses `source` and prints it's only statement as an s-expression
+        fn parse(source: &str) -> String {
+            let ast = Ast::from_source(source);
+            match &ast.tree[ast.root] {
+                Node::Program(body) if body.len() == 1 => sexpr(&ast, body[0]),
+                node => panic!("expected a program with one statement, found {:?}", node),
             }
         }
+
+        #[test]
+        fn binary_precedence() {
+            assert_eq!(parse("1 + 2 * 3"), "(+ 1 (* 2 3))");
+            assert_eq!(parse("(1 + 2) * 3"), "(* (+ 1 2) 3)");
+            assert_eq!(parse("1 < 2 == 3 > 4"), "(== (< 1 2) (> 3 4))");
+            assert_eq!(parse("a or b and c"), "(or a (and b c))");
+        }
+
+        #[test]
+        fn left_associative_operators() {
+            assert_eq!(parse("1 - 2 - 3"), "(- (- 1 2) 3)");
+            assert_eq!(parse("8 / 4 / 2"), "(/ (/ 8 4) 2)");
+        }
+
+        #[test]
+        fn power_is_right_associative() {
+            assert_eq!(parse("2 ^ 3 ^ 2"), "(^ 2 (^ 3 2))");
+            assert_eq!(parse("2 * 3 ^ 2"), "(* 2 (^ 3 2))");
+        }
+
+        #[test]
+        fn prefix_precedence() {
+            assert_eq!(parse("-2 ^ 2"), "(- (^ 2 2))");
+            assert_eq!(parse("-2 * 3"), "(* (- 2) 3)");
+            assert_eq!(parse("not a == b"), "(not (== a b))");
+            assert_eq!(parse("!a == b"), "(== (not a) b)");
+        }
     }
-}
\ No newline at end of file
+}
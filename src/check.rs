@@ -0,0 +1,562 @@
+pub mod check {
+    use std::{collections::HashMap, fmt};
+    use crate::ast::{ast::{Ast, NodeKey}, node::{BinaryOp, Call, LiteralType, Node, ProcDecl, Type, UnaryOp}};
+    use crate::error::error::{self, Diagnostic, Span};
+
+    // What is known about the value of an expression before it runs
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Inferred {
+        Known(Type),
+        // An integer or a number depending on the values, like `7 / 2`
+        Numeric,
+        // Produced by calling a procedure with no return type
+        Nothing,
+        // Couldn't be worked out, so nothing is checked against it
+        Unknown,
+    }
+
+    impl Inferred {
+        fn is_numeric(&self) -> bool {
+            matches!(self, Inferred::Known(Type::Integer | Type::Number) | Inferred::Numeric)
+        }
+
+        // Whether the value can be stored as `typ`, following the same
+        // rules as `coerce` in the evaluator
+        fn fits(&self, typ: Type) -> bool {
+            match self {
+                Inferred::Known(Type::Integer) | Inferred::Numeric => matches!(typ, Type::Integer | Type::Number),
+                Inferred::Known(known) => *known == typ,
+                Inferred::Nothing => false,
+                Inferred::Unknown => true,
+            }
+        }
+
+        // Whether the value can be stored in a variable inferred as `self`
+        fn accepts(&self, value: Inferred) -> bool {
+            match self {
+                Inferred::Known(typ) => value.fits(*typ),
+                Inferred::Numeric => value.is_numeric() || value == Inferred::Unknown,
+                Inferred::Nothing | Inferred::Unknown => true,
+            }
+        }
+    }
+
+    impl fmt::Display for Inferred {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Inferred::Known(typ) => write!(f, "{}", typ),
+                Inferred::Numeric => write!(f, "number"),
+                Inferred::Nothing => write!(f, "nothing"),
+                Inferred::Unknown => write!(f, "unknown"),
+            }
+        }
+    }
+
+    // Parameter and return types of a declared procedure
+    #[derive(Debug, Clone)]
+    struct Signature {
+        name: String,
+        params: Vec<Type>,
+        returns: Option<Type>,
+    }
+
+    // A variable in scope, along with the signature if it names a procedure
+    #[derive(Debug, Clone)]
+    struct Local {
+        inferred: Inferred,
+        signature: Option<Signature>,
+    }
+
+    // Variables declared so far, kept between entries by the REPL
+    #[derive(Debug, Clone)]
+    pub struct Scopes(Vec<HashMap<String, Local>>);
+
+    impl Default for Scopes {
+        fn default() -> Self {
+            Self(vec![HashMap::new()])
+        }
+    }
+
+    // Walks the tree before it is evaluated, working out the type of each
+    // expression and reporting mismatches the evaluator would hit, such as
+    // `"a" - 1`, annotations that don't match their value and calls that
+    // don't match the procedure's signature
+    // Variables that can't be resolved are left to the evaluator, since
+    // procedures may use variables declared after them
+    // For the same reason a procedure body only resolves it's parameters and
+    // locals, anything else is looked up in the scope it was declared in when
+    // it is called, which may have changed by then
+    pub struct Checker<'a> {
+        ast: &'a Ast,
+        scopes: Vec<HashMap<String, Local>>,
+        // Signature of the procedure whose body is being checked, and the
+        // index of the scope holding it's parameters
+        current_proc: Option<Signature>,
+        proc_scope: usize,
+        diagnostics: Vec<Diagnostic>,
+    }
+
+    impl<'a> Checker<'a> {
+        pub fn new(ast: &'a Ast) -> Self {
+            Self::resume(ast, Scopes::default())
+        }
+
+        // Carries on checking with the variables declared by an earlier checker
+        pub fn resume(ast: &'a Ast, scopes: Scopes) -> Self {
+            Self { ast, scopes: scopes.0, current_proc: None, proc_scope: 0, diagnostics: Vec::new() }
+        }
+
+        pub fn into_scopes(self) -> Scopes {
+            Scopes(self.scopes)
+        }
+
+        // Checks every statement owned by the program node, collecting
+        // every mismatch in the file rather than stopping at the first
+        pub fn check(&mut self) -> Result<(), Vec<Diagnostic>> {
+            let body = match &self.ast.tree[self.ast.root] {
+                Node::Program(body) => body,
+                _ => panic!("Root of the tree is not a program node"),
+            };
+
+            self.check_statements(body)
+        }
+
+        pub fn check_statements(&mut self, body: &[NodeKey]) -> Result<(), Vec<Diagnostic>> {
+            for key in body {
+                self.check_node(*key);
+            }
+
+            if self.diagnostics.is_empty() {
+                Ok(())
+            } else {
+                Err(std::mem::take(&mut self.diagnostics))
+            }
+        }
+
+        // Name of the type an expression will have when it runs
+        pub fn type_of(&mut self, key: NodeKey) -> Result<String, Vec<Diagnostic>> {
+            let inferred = self.check_node(key);
+            if !self.diagnostics.is_empty() {
+                return Err(std::mem::take(&mut self.diagnostics));
+            }
+
+            Ok(match inferred {
+                Inferred::Numeric => String::from("integer or number"),
+                inferred => inferred.to_string(),
+            })
+        }
+
+        fn check_node(&mut self, key: NodeKey) -> Inferred {
+            let span = self.ast.span(key);
+            match &self.ast.tree[key] {
+                Node::BinaryExpr(expr) => {
+                    let lt = self.check_node(expr.ln);
+                    let rt = self.check_node(expr.rn);
+                    self.report(binary(&expr.op, lt, rt), span)
+                },
+                Node::UnaryExpr(expr) => {
+                    let rt = self.check_node(expr.rn);
+                    self.report(unary(&expr.op, rt), span)
+                },
+                Node::Grouping(inner) => self.check_node(*inner),
+
+                // Literals
+                Node::StringLitExpr(lit) | Node::NumberLitExpr(lit) | Node::IntegerLitExpr(lit) | Node::BooleanLitExpr(lit) => {
+                    Inferred::Known(match lit.typ {
+                        LiteralType::String(_) => Type::String,
+                        LiteralType::Number(_) => Type::Number,
+                        LiteralType::Integer(_) | LiteralType::BigInteger(_) => Type::Integer,
+                        LiteralType::Boolean(_) => Type::Boolean,
+                    })
+                },
+                Node::Concat(parts) => {
+                    for part in parts {
+                        self.check_node(*part);
+                    }
+                    Inferred::Known(Type::String)
+                },
+
+                Node::Cast(cast) => {
+                    let value = self.check_node(cast.value);
+                    let convertible = match value {
+                        Inferred::Known(Type::Procedure) => matches!(cast.typ, Type::String | Type::Boolean),
+                        Inferred::Nothing => false,
+                        _ => true,
+                    };
+                    if !convertible {
+                        let what = match value {
+                            Inferred::Nothing => String::from("nothing"),
+                            value => format!("a {}", value),
+                        };
+                        self.error(error::INVALID_CONVERSION, format!("Cannot convert {} to {}", what, cast.typ), span);
+                    }
+                    Inferred::Known(cast.typ)
+                },
+
+                // Variables
+                Node::Ident(name) => match self.lookup(name) {
+                    Some(local) => local.inferred,
+                    None => Inferred::Unknown,
+                },
+                Node::VarDecl(decl) => {
+                    let value = self.check_node(decl.value);
+                    let inferred = match (decl.typ, value) {
+                        (Some(typ), value) => {
+                            if !value.fits(typ) {
+                                self.error(error::TYPE_MISMATCH, format!("Expected a value of type {} but found {}", typ, value), span);
+                            }
+                            Inferred::Known(typ)
+                        },
+                        (None, Inferred::Nothing) => {
+                            self.error(error::TYPE_MISMATCH, format!("Cannot store nothing in `{}`", decl.name), span);
+                            Inferred::Unknown
+                        },
+                        (None, value) => value,
+                    };
+
+                    // Keep the signature when a procedure is copied into a variable
+                    let signature = match &self.ast.tree[decl.value] {
+                        Node::Ident(name) => self.lookup(name).and_then(|local| local.signature),
+                        _ => None,
+                    };
+                    self.declare(&decl.name, Local { inferred, signature });
+                    Inferred::Nothing
+                },
+                Node::Assign(assign) => {
+                    let value = self.check_node(assign.value);
+                    if let Some(local) = self.lookup(&assign.name) {
+                        if !local.inferred.accepts(value) {
+                            self.error(
+                                error::TYPE_MISMATCH,
+                                format!("Cannot reassign `{}`: Expected a value of type {} but found {}", assign.name, local.inferred, value),
+                                span,
+                            );
+                        }
+                    }
+                    Inferred::Nothing
+                },
+
+                // Procedures
+                Node::ProcDecl(decl) => {
+                    self.check_proc_decl(decl, span);
+                    Inferred::Nothing
+                },
+                Node::Call(call) => self.check_call(call, span),
+                Node::Return(value) => {
+                    let inferred = match value {
+                        Some(value) => self.check_node(*value),
+                        None => Inferred::Nothing,
+                    };
+                    self.check_return(inferred, span);
+                    Inferred::Nothing
+                },
+
+                Node::Print(values) => {
+                    for value in values {
+                        self.check_node(*value);
+                    }
+                    Inferred::Nothing
+                },
+
+                Node::If(stmt) => {
+                    self.check_node(stmt.cond);
+                    self.check_node(stmt.then);
+                    if let Some(otherwise) = stmt.otherwise {
+                        self.check_node(otherwise);
+                    }
+                    Inferred::Nothing
+                },
+
+                Node::While(stmt) => {
+                    self.check_node(stmt.cond);
+                    self.check_node(stmt.body);
+                    Inferred::Nothing
+                },
+                Node::For(stmt) => {
+                    for bound in [stmt.start, stmt.end] {
+                        let inferred = self.check_node(bound);
+                        if !matches!(inferred, Inferred::Known(Type::Integer) | Inferred::Numeric | Inferred::Unknown) {
+                            self.error(
+                                error::TYPE_MISMATCH,
+                                format!("Ranges must be made of integers but found {}", inferred),
+                                self.ast.span(bound),
+                            );
+                        }
+                    }
+
+                    self.scopes.push(HashMap::new());
+                    self.declare(&stmt.var, Local { inferred: Inferred::Known(Type::Integer), signature: None });
+                    self.check_node(stmt.body);
+                    self.scopes.pop();
+                    Inferred::Nothing
+                },
+                Node::Break | Node::Continue => Inferred::Nothing,
+
+                Node::Block(body) => {
+                    self.scopes.push(HashMap::new());
+                    for key in body {
+                        self.check_node(*key);
+                    }
+                    self.scopes.pop();
+                    Inferred::Nothing
+                },
+
+                Node::Program(_) => panic!("Cannot check a nested program node"),
+            }
+        }
+
+        // Declares the procedure before checking it's body so it can call itself
+        fn check_proc_decl(&mut self, decl: &ProcDecl, span: Span) {
+            let signature = Signature {
+                name: decl.name.clone(),
+                params: decl.params.iter().map(|param| param.typ).collect(),
+                returns: decl.returns,
+            };
+            self.declare(&decl.name, Local { inferred: Inferred::Known(Type::Procedure), signature: Some(signature.clone()) });
+
+            let caller_scope = std::mem::replace(&mut self.proc_scope, self.scopes.len());
+            self.scopes.push(HashMap::new());
+            for param in &decl.params {
+                self.declare(&param.name, Local { inferred: Inferred::Known(param.typ), signature: None });
+            }
+            let caller = self.current_proc.replace(signature);
+            self.check_node(decl.body);
+            self.current_proc = caller;
+            self.scopes.pop();
+            self.proc_scope = caller_scope;
+
+            // Whether every path returns isn't tracked, but a body without
+            // any `return` can never produce the value it promises
+            if let Some(typ) = decl.returns {
+                if !has_return(self.ast, decl.body) {
+                    self.error(
+                        error::MISSING_RETURN,
+                        format!("`{}` should return a value of type {} but has no `return`", decl.name, typ),
+                        span,
+                    );
+                }
+            }
+        }
+
+        // Checks the arguments of a call against the procedure's signature,
+        // if it is known, and returns what the call produces
+        fn check_call(&mut self, call: &Call, span: Span) -> Inferred {
+            let callee = self.check_node(call.callee);
+            let args: Vec<Inferred> = call.args.iter().map(|arg| self.check_node(*arg)).collect();
+
+            if !matches!(callee, Inferred::Known(Type::Procedure) | Inferred::Unknown) {
+                self.error(
+                    error::NOT_CALLABLE,
+                    format!("Cannot call a value of type {}", callee),
+                    self.ast.span(call.callee),
+                );
+                return Inferred::Unknown;
+            }
+
+            let signature = match &self.ast.tree[call.callee] {
+                Node::Ident(name) => self.lookup(name).and_then(|local| local.signature),
+                _ => None,
+            };
+            let signature = match signature {
+                Some(signature) => signature,
+                None => return Inferred::Unknown,
+            };
+
+            if args.len() != signature.params.len() {
+                self.error(
+                    error::ARGUMENT_COUNT,
+                    format!(
+                        "`{}` takes {} argument(s) but {} were given",
+                        signature.name, signature.params.len(), args.len()
+                    ),
+                    span,
+                );
+            } else {
+                for ((arg, inferred), typ) in call.args.iter().zip(args).zip(&signature.params) {
+                    if !inferred.fits(*typ) {
+                        self.error(
+                            error::TYPE_MISMATCH,
+                            format!("Invalid argument for `{}`: Expected a value of type {} but found {}", signature.name, typ, inferred),
+                            self.ast.span(*arg),
+                        );
+                    }
+                }
+            }
+
+            match signature.returns {
+                Some(typ) => Inferred::Known(typ),
+                None => Inferred::Nothing,
+            }
+        }
+
+        // Checks a returned value against the return type of the procedure
+        // it is returned from
+        fn check_return(&mut self, inferred: Inferred, span: Span) {
+            let signature = match &self.current_proc {
+                Some(signature) => signature.clone(),
+                None => return,
+            };
+
+            match (signature.returns, inferred) {
+                (_, Inferred::Unknown) | (None, Inferred::Nothing) => {},
+                (None, inferred) => self.error(
+                    error::TYPE_MISMATCH,
+                    format!("`{}` has no return type but returns a value of type {}", signature.name, inferred),
+                    span,
+                ),
+                (Some(typ), Inferred::Nothing) => self.error(
+                    error::MISSING_RETURN,
+                    format!("`{}` should return a value of type {} but `return` has no value", signature.name, typ),
+                    span,
+                ),
+                (Some(typ), inferred) if !inferred.fits(typ) => self.error(
+                    error::TYPE_MISMATCH,
+                    format!("Invalid return value from `{}`: Expected a value of type {} but found {}", signature.name, typ, inferred),
+                    span,
+                ),
+                _ => {},
+            }
+        }
+
+        fn declare(&mut self, name: &str, local: Local) {
+            if let Some(scope) = self.scopes.last_mut() {
+                scope.insert(name.to_string(), local);
+            }
+        }
+
+        // Looks for a variable in the innermost scope, then each enclosing scope
+        // up to the parameters of the procedure being checked, if any
+        fn lookup(&self, name: &str) -> Option<Local> {
+            self.scopes[self.proc_scope..].iter().rev().find_map(|scope| scope.get(name)).cloned()
+        }
+
+        // Records the error of an operation, if any, and returns what it produces
+        fn report(&mut self, result: Result<Inferred, String>, span: Span) -> Inferred {
+            match result {
+                Ok(inferred) => inferred,
+                Err(message) => {
+                    self.error(error::TYPE_MISMATCH, message, span);
+                    Inferred::Unknown
+                },
+            }
+        }
+
+        fn error(&mut self, code: &'static str, message: String, span: Span) {
+            self.diagnostics.push(Diagnostic::error(code, message, span));
+        }
+    }
+
+    // Whether there is a `return` anywhere beneath `key`, not counting the
+    // bodies of procedures declared inside it
+    fn has_return(ast: &Ast, key: NodeKey) -> bool {
+        match &ast.tree[key] {
+            Node::Return(_) => true,
+            Node::ProcDecl(_) => false,
+            node => node.children().into_iter().any(|child| has_return(ast, child)),
+        }
+    }
+
+    // Works out what a unary operator produces, mirroring `unary` in the evaluator
+    fn unary(op: &UnaryOp, rt: Inferred) -> Result<Inferred, String> {
+        match (op, rt) {
+            (UnaryOp::Negate, Inferred::Unknown) => Ok(Inferred::Unknown),
+            (UnaryOp::Negate, rt) if rt.is_numeric() => Ok(rt),
+            (UnaryOp::Negate, rt) => Err(format!("Cannot negate a {}", rt)),
+            (UnaryOp::Not, _) => Ok(Inferred::Known(Type::Boolean)),
+        }
+    }
+
+    // Works out what a binary operator produces, mirroring `binary` in the evaluator
+    fn binary(op: &BinaryOp, lt: Inferred, rt: Inferred) -> Result<Inferred, String> {
+        let boolean = Inferred::Known(Type::Boolean);
+        match op {
+            BinaryOp::Equal | BinaryOp::NotEqual | BinaryOp::And | BinaryOp::Or => Ok(boolean),
+            BinaryOp::Less | BinaryOp::LessEqual | BinaryOp::Greater | BinaryOp::GreaterEqual => match (lt, rt) {
+                (Inferred::Unknown, _) | (_, Inferred::Unknown) => Ok(boolean),
+                (Inferred::Known(Type::String), Inferred::Known(Type::String)) => Ok(boolean),
+                (lt, rt) if lt.is_numeric() && rt.is_numeric() => Ok(boolean),
+                (lt, rt) => Err(format!("Cannot compare {} and {} with `{}`", lt, rt, op)),
+            },
+            _ => arithmetic(op, lt, rt),
+        }
+    }
+
+    // Follows the promotion rules of `arithmetic` in the evaluator, except
+    // that `/` and `^` on two integers may produce either an integer or a
+    // number depending on the values
+    fn arithmetic(op: &BinaryOp, lt: Inferred, rt: Inferred) -> Result<Inferred, String> {
+        match (lt, rt) {
+            (Inferred::Unknown, _) | (_, Inferred::Unknown) => Ok(Inferred::Unknown),
+            (Inferred::Known(Type::String), Inferred::Known(Type::String)) if *op == BinaryOp::Plus => {
+                Ok(Inferred::Known(Type::String))
+            },
+            (lt, rt) if lt.is_numeric() && rt.is_numeric() => Ok(match (lt, rt) {
+                (Inferred::Known(Type::Number), _) | (_, Inferred::Known(Type::Number)) => Inferred::Known(Type::Number),
+                (Inferred::Known(Type::Integer), Inferred::Known(Type::Integer)) if !matches!(op, BinaryOp::Divide | BinaryOp::Power) => {
+                    Inferred::Known(Type::Integer)
+                },
+                _ => Inferred::Numeric,
+            }),
+            (lt, rt) => Err(format!("Cannot apply `{}` to {} and {}", op, lt, rt)),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use crate::ast::ast::Ast;
+        use crate::error::error;
+        use super::Checker;
+
+        // Checks `source`, returning the code of each error found
+        fn check(source: &str) -> Vec<&'static str> {
+            let ast = Ast::from_source(source);
+            match Checker::new(&ast).check() {
+                Ok(()) => Vec::new(),
+                Err(diagnostics) => diagnostics.iter().map(|d| d.code).collect(),
+            }
+        }
+
+        #[test]
+        fn operands_must_fit_the_operator() {
+            assert_eq!(check("x = \"a\" - 1\n"), [error::TYPE_MISMATCH]);
+            assert!(check("x = \"a\" + \"b\"\ny = 1 + 2.5\n").is_empty());
+        }
+
+        #[test]
+        fn values_must_fit_their_annotation() {
+            assert_eq!(check("x: integer = \"a\"\n"), [error::TYPE_MISMATCH]);
+            assert_eq!(check("x = 1\nx -> \"a\"\n"), [error::TYPE_MISMATCH]);
+            assert!(check("x: number = 1\n").is_empty());
+        }
+
+        #[test]
+        fn calls_must_match_the_signature() {
+            let proc = "proc f(a: integer) returns integer {\n    return a\n}\n";
+            assert!(check(&format!("{}f(1)\n", proc)).is_empty());
+            assert_eq!(check(&format!("{}f(1, 2)\n", proc)), [error::ARGUMENT_COUNT]);
+            assert_eq!(check(&format!("{}f(\"a\")\n", proc)), [error::TYPE_MISMATCH]);
+            assert_eq!(check("x = 1\nx(2)\n"), [error::NOT_CALLABLE]);
+        }
+
+        #[test]
+        fn return_values_must_match_the_signature() {
+            assert_eq!(check("proc f() returns integer {\n    return \"a\"\n}\n"), [error::TYPE_MISMATCH]);
+            assert_eq!(check("proc f() {\n    return 1\n}\n"), [error::TYPE_MISMATCH]);
+            assert_eq!(check("proc f() returns integer {\n    return\n}\n"), [error::MISSING_RETURN]);
+        }
+
+        #[test]
+        fn procedures_without_a_return_are_reported() {
+            assert_eq!(check("proc g() returns integer {\n    print 1\n}\n"), [error::MISSING_RETURN]);
+            assert!(check("proc g(n: integer) returns integer {\n    if n > 0 {\n        return 1\n    }\n    return 0\n}\n").is_empty());
+        }
+
+        // `x` is looked up when `f` is called, by which point the block has
+        // shadowed the outer string with an integer
+        #[test]
+        fn procedures_resolve_outer_variables_when_called() {
+            let source = "x = \"s\"\n{\n    proc f() returns integer {\n        return x\n    }\n    x = 1\n    print f()\n}\n";
+            assert!(check(source).is_empty());
+        }
+    }
+}
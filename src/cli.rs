@@ -0,0 +1,157 @@
+pub mod cli {
+    pub const USAGE: &str = "\
+Usage: darcy2 [command] [options] [file]
+
+Runs a Darcy program, or starts the REPL if no file is given
+Use `-` as the file to read the program from stdin, which is also read if
+it is piped in and no file is given
+
+Commands:
+  run       Check and run the program, the default
+  tokens    Print the tokens the lexer produces, comments included
+  ast       Print the tree the parser produces, one node per line
+  check     Report any errors without running the program
+
+Options:
+  --emit=<stage>   Stop after a stage and print what it produced,
+                   one of tokens, ast, dot or bytecode
+                   dot is the tree as a Graphviz graph, e.g.
+                   `darcy2 --emit=dot main.dy | dot -Tsvg > ast.svg`
+  --no-color       Don't colour diagnostics
+  -h, --help       Show this message";
+
+    // How far through the pipeline to go and what to print at the end
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Command {
+        Run,
+        Tokens,
+        Ast,
+        // The tree as a Graphviz graph, only reached through --emit=dot
+        Dot,
+        Check,
+    }
+
+    impl Command {
+        pub fn name(&self) -> &'static str {
+            match self {
+                Command::Run => "run",
+                Command::Tokens => "tokens",
+                Command::Ast => "ast",
+                Command::Dot => "dot",
+                Command::Check => "check",
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Options {
+        pub command: Command,
+        // `-` means stdin, None means stdin if it is piped and the REPL otherwise
+        pub path: Option<String>,
+        pub no_color: bool,
+        pub help: bool,
+    }
+
+    // Reads the command, flags and file from the arguments after the program name
+    // The command can only be the first argument so a file called `ast` can still be run
+    pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+        let mut args = args.into_iter().peekable();
+        let mut command = match args.peek().map(String::as_str) {
+            Some("run") => Some(Command::Run),
+            Some("tokens") => Some(Command::Tokens),
+            Some("ast") => Some(Command::Ast),
+            Some("check") => Some(Command::Check),
+            _ => None,
+        };
+        if command.is_some() {
+            args.next();
+        }
+
+        let mut options = Options { command: Command::Run, path: None, no_color: false, help: false };
+        for arg in args {
+            match arg.as_str() {
+                "-h" | "--help" => options.help = true,
+                "--no-color" => options.no_color = true,
+                _ if arg.starts_with("--emit=") => {
+                    let emit = match &arg["--emit=".len()..] {
+                        "tokens" => Command::Tokens,
+                        "ast" => Command::Ast,
+                        "dot" => Command::Dot,
+                        "bytecode" => return Err(String::from(
+                            "Cannot emit bytecode, programs are run straight from the tree and there is no bytecode compiler yet"
+                        )),
+                        stage => return Err(format!("Unknown stage `{}` for --emit, expected tokens, ast, dot or bytecode", stage)),
+                    };
+                    command = match command {
+                        Some(Command::Run) | None => Some(emit),
+                        Some(other) if other == emit => Some(emit),
+                        Some(other) => return Err(format!("--emit={} cannot be used with the `{}` command", &arg["--emit=".len()..], other.name())),
+                    };
+                },
+                "-" => options.path = set_path(options.path, arg)?,
+                _ if arg.starts_with('-') => return Err(format!("Unknown option `{}`", arg)),
+                _ => options.path = set_path(options.path, arg)?,
+            }
+        }
+
+        options.command = command.unwrap_or(Command::Run);
+        Ok(options)
+    }
+
+    fn set_path(path: Option<String>, arg: String) -> Result<Option<String>, String> {
+        match path {
+            Some(path) => Err(format!("Expected one file but got `{}` and `{}`", path, arg)),
+            None => Ok(Some(arg)),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{parse_args, Command, Options};
+
+        fn parse(args: &[&str]) -> Result<Options, String> {
+            parse_args(args.iter().map(|arg| arg.to_string()))
+        }
+
+        #[test]
+        fn command_is_only_the_first_argument() {
+            let options = parse(&["ast", "main.dy"]).unwrap();
+            assert_eq!((options.command, options.path.as_deref()), (Command::Ast, Some("main.dy")));
+
+            let options = parse(&["--no-color", "ast"]).unwrap();
+            assert_eq!((options.command, options.path.as_deref()), (Command::Run, Some("ast")));
+            assert!(options.no_color);
+
+            let options = parse(&[]).unwrap();
+            assert_eq!((options.command, options.path), (Command::Run, None));
+        }
+
+        #[test]
+        fn emit_picks_the_command() {
+            assert_eq!(parse(&["--emit=tokens", "main.dy"]).unwrap().command, Command::Tokens);
+            assert_eq!(parse(&["run", "--emit=dot", "main.dy"]).unwrap().command, Command::Dot);
+            assert_eq!(parse(&["ast", "--emit=ast", "main.dy"]).unwrap().command, Command::Ast);
+        }
+
+        #[test]
+        fn emit_conflicting_with_the_command_is_rejected() {
+            assert!(parse(&["tokens", "--emit=ast", "main.dy"]).is_err());
+            assert!(parse(&["check", "--emit=dot", "main.dy"]).is_err());
+        }
+
+        #[test]
+        fn unsupported_arguments_are_rejected() {
+            assert!(parse(&["--emit=bytecode", "main.dy"]).is_err());
+            assert!(parse(&["--emit=llvm", "main.dy"]).is_err());
+            assert!(parse(&["--colour", "main.dy"]).is_err());
+            assert!(parse(&["a.dy", "b.dy"]).is_err());
+            assert!(parse(&["-", "main.dy"]).is_err());
+        }
+
+        #[test]
+        fn dash_reads_stdin() {
+            let options = parse(&["check", "-"]).unwrap();
+            assert_eq!((options.command, options.path.as_deref()), (Command::Check, Some("-")));
+        }
+    }
+}
@@ -0,0 +1,195 @@
+pub mod error {
+    use std::fmt;
+
+    // Diagnostic codes, grouped by the stage that reports them
+    // Lexer
+    pub const UNTERMINATED_STRING: &str = "E0001";
+    pub const UNEXPECTED_CHAR: &str = "E0002";
+    pub const UNTERMINATED_COMMENT: &str = "E0003";
+    pub const MALFORMED_NUMBER: &str = "E0004";
+    pub const INVALID_ESCAPE: &str = "E0005";
+    pub const UNCLOSED_INTERPOLATION: &str = "E0006";
+
+    // Parser
+    pub const EMPTY_STREAM: &str = "E0100";
+    pub const INVALID_NUMBER: &str = "E0101";
+    pub const EXPECTED_EXPR: &str = "E0102";
+    pub const EXPECTED_TOKEN: &str = "E0103";
+    pub const RETURN_OUTSIDE_PROC: &str = "E0104";
+    pub const CONTROL_OUTSIDE_LOOP: &str = "E0105";
+    pub const EXPECTED_NEWLINE: &str = "E0106";
+    pub const UNMATCHED_PAREN: &str = "E0107";
+    pub const DUPLICATE_PARAM: &str = "E0108";
+    pub const DANGLING_ELSE: &str = "E0109";
+
+    // Runtime, some of which are also found ahead of time by the type checker
+    pub const TYPE_MISMATCH: &str = "E0200";
+    pub const DIVIDE_BY_ZERO: &str = "E0201";
+    pub const INTEGER_OVERFLOW: &str = "E0202";
+    pub const UNDEFINED_VARIABLE: &str = "E0203";
+    pub const ALREADY_DECLARED: &str = "E0204";
+    pub const NOT_CALLABLE: &str = "E0205";
+    pub const ARGUMENT_COUNT: &str = "E0206";
+    pub const MISSING_RETURN: &str = "E0207";
+    pub const OUTPUT_FAILED: &str = "E0208";
+    pub const INVALID_CONVERSION: &str = "E0209";
+    pub const CALL_DEPTH: &str = "E0210";
+    pub const INVALID_POWER: &str = "E0211";
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Severity {
+        Error,
+        #[allow(dead_code)]
+        Warning,
+    }
+
+    // Location of a token, node or diagnostic in the source file
+    // `start` and `end` are byte offsets, lines and columns start at 1 and
+    // `end_col` is the column just past the last character
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct Span {
+        pub start: usize,
+        pub end: usize,
+        pub line: usize,
+        pub col: usize,
+        pub end_line: usize,
+        pub end_col: usize,
+    }
+
+    impl Span {
+        // Span covering a whole line when no column is known
+        pub fn line(line: usize) -> Self {
+            Self { line, end_line: line, ..Self::default() }
+        }
+
+        // Span from the start of self to the end of other
+        pub fn to(self, other: Span) -> Span {
+            Span {
+                end: other.end,
+                end_line: other.end_line,
+                end_col: other.end_col,
+                ..self
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Diagnostic {
+        pub severity: Severity,
+        pub code: &'static str,
+        pub message: String,
+        pub span: Span,
+    }
+
+    impl Diagnostic {
+        pub fn error(code: &'static str, message: String, span: Span) -> Self {
+            Self { severity: Severity::Error, code, message, span }
+        }
+    }
+
+    impl fmt::Display for Severity {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Severity::Error => write!(f, "error"),
+                Severity::Warning => write!(f, "warning"),
+            }
+        }
+    }
+
+    impl fmt::Display for Diagnostic {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}[{}] ({}:{}): {}", self.severity, self.code, self.span.line, self.span.col, self.message)
+        }
+    }
+
+    // ANSI escape codes used when colour is enabled
+    const RED: &str = "\x1b[1;31m";
+    const YELLOW: &str = "\x1b[1;33m";
+    const BLUE: &str = "\x1b[1;34m";
+    const BOLD: &str = "\x1b[1m";
+    const RESET: &str = "\x1b[0m";
+
+    // Renders diagnostics rustc style, showing the file, line and column,
+    // the offending source line and a `^^^` underline beneath the span
+    pub struct Renderer<'a> {
+        file: &'a str,
+        source: &'a [String],
+        color: bool,
+    }
+
+    impl<'a> Renderer<'a> {
+        pub fn new(file: &'a str, source: &'a [String], color: bool) -> Self {
+            Self { file, source, color }
+        }
+
+        pub fn render(&self, diagnostic: &Diagnostic) -> String {
+            let level_color = match diagnostic.severity {
+                Severity::Error => RED,
+                Severity::Warning => YELLOW,
+            };
+
+            let mut out = format!(
+                "{}{}[{}]{}{}: {}{}\n",
+                self.paint(level_color), diagnostic.severity, diagnostic.code, self.paint(RESET),
+                self.paint(BOLD), diagnostic.message, self.paint(RESET),
+            );
+
+            // Find the offending line, if the span points at one
+            let line = diagnostic.span.line;
+            let content = match line.checked_sub(1).and_then(|i| self.source.get(i)) {
+                Some(content) => content.trim_end_matches('\r'),
+                None => {
+                    out.push_str(&format!("{}--> {}{}:{}\n", self.paint(BLUE), self.paint(RESET), self.file, line));
+                    return out;
+                }
+            };
+
+            // Underline the span, or everything on the line but the surrounding
+            // whitespace if there is no column. Spans running onto later lines
+            // are underlined to the end of the first
+            let line_len = content.chars().count();
+            let (start, len) = if diagnostic.span.col == 0 {
+                let start = content.chars().take_while(|c| c.is_whitespace()).count();
+                (start, content.trim().chars().count())
+            } else {
+                let start = diagnostic.span.col - 1;
+                let end = if diagnostic.span.end_line == line {
+                    diagnostic.span.end_col - 1
+                } else {
+                    line_len
+                };
+                (start, end.min(line_len).saturating_sub(start))
+            };
+            let len = len.max(1);
+
+            // Pad up to the span with the line's own tabs so the underline
+            // lines up however wide the terminal draws a tab
+            let padding: String = content.chars()
+                .chain(std::iter::repeat(' '))
+                .take(start)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+
+            let gutter = " ".repeat(line.to_string().len());
+            out.push_str(&format!("{}{}--> {}{}:{}:{}\n", gutter, self.paint(BLUE), self.paint(RESET), self.file, line, start + 1));
+            out.push_str(&format!("{} {}|{}\n", gutter, self.paint(BLUE), self.paint(RESET)));
+            out.push_str(&format!("{}{} |{} {}\n", self.paint(BLUE), line, self.paint(RESET), content));
+            out.push_str(&format!(
+                "{} {}|{} {}{}{}{}\n",
+                gutter, self.paint(BLUE), self.paint(RESET),
+                padding, self.paint(level_color), "^".repeat(len), self.paint(RESET),
+            ));
+
+            out
+        }
+
+        // Returns the escape code if colour is enabled, otherwise nothing
+        fn paint(&self, code: &'static str) -> &'static str {
+            if self.color {
+                code
+            } else {
+                ""
+            }
+        }
+    }
+}
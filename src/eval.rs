@@ -0,0 +1,1066 @@
+pub mod value {
+    use std::{cell::RefCell, fmt, rc::Rc};
+    use num_bigint::BigInt;
+    use num_traits::{ToPrimitive, Zero};
+    use crate::ast::{ast::NodeKey, node::{Param, Type}};
+    use super::env::Scope;
+
+    // Runtime values produced by the evaluator
+    // `Nothing` is produced by statements like declarations
+    // Integers are 64 bit until they outgrow it, at which point they become
+    // a `BigInteger`. Both have the type `integer` and a `BigInteger` is
+    // always outside the range of an `i64`, see `From<BigInt>`
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Value {
+        Integer(i64),
+        BigInteger(BigInt),
+        Number(f64),
+        String(String),
+        Boolean(bool),
+        Proc(Rc<Procedure>),
+        Nothing,
+    }
+
+    // A declared procedure along with the scope it was declared in,
+    // which it's body runs inside of when called
+    pub struct Procedure {
+        pub name: String,
+        pub params: Vec<Param>,
+        pub returns: Option<Type>,
+        pub body: NodeKey,
+        pub closure: Rc<RefCell<Scope>>,
+    }
+
+    // Procedures are only equal to themselves
+    impl PartialEq for Procedure {
+        fn eq(&self, other: &Self) -> bool {
+            std::ptr::eq(self, other)
+        }
+    }
+
+    // Skips the closure, which would print every variable in scope
+    impl fmt::Debug for Procedure {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "Procedure({})", self.name)
+        }
+    }
+
+    impl Value {
+        // Type of the value, `None` for `Nothing`
+        pub fn typ(&self) -> Option<Type> {
+            match self {
+                Value::Integer(_) | Value::BigInteger(_) => Some(Type::Integer),
+                Value::Number(_) => Some(Type::Number),
+                Value::String(_) => Some(Type::String),
+                Value::Boolean(_) => Some(Type::Boolean),
+                Value::Proc(_) => Some(Type::Procedure),
+                Value::Nothing => None,
+            }
+        }
+
+        // Whether the value counts as true in a condition
+        // `false`, zero, the empty string and nothing are false, everything else is true
+        pub fn is_truthy(&self) -> bool {
+            match self {
+                Value::Integer(n) => *n != 0,
+                Value::BigInteger(n) => !n.is_zero(),
+                Value::Number(n) => *n != 0.0,
+                Value::String(s) => !s.is_empty(),
+                Value::Boolean(b) => *b,
+                Value::Proc(_) => true,
+                Value::Nothing => false,
+            }
+        }
+
+        // Value of an integer or number as a float, `None` for anything else
+        pub fn as_number(&self) -> Option<f64> {
+            match self {
+                Value::Integer(n) => Some(*n as f64),
+                Value::BigInteger(n) => Some(n.to_f64().unwrap_or(f64::NAN)),
+                Value::Number(n) => Some(*n),
+                _ => None,
+            }
+        }
+
+        // Value of an integer as a bignum, `None` for anything else
+        pub fn as_big_integer(&self) -> Option<BigInt> {
+            match self {
+                Value::Integer(n) => Some(BigInt::from(*n)),
+                Value::BigInteger(n) => Some(n.clone()),
+                _ => None,
+            }
+        }
+
+        // Name of the value's type as the user would write it
+        pub fn type_name(&self) -> String {
+            match self.typ() {
+                Some(typ) => typ.to_string(),
+                None => String::from("nothing"),
+            }
+        }
+    }
+
+    // Shrinks bignums back down to 64 bit integers whenever they fit
+    impl From<BigInt> for Value {
+        fn from(n: BigInt) -> Self {
+            match n.to_i64() {
+                Some(n) => Value::Integer(n),
+                None => Value::BigInteger(n),
+            }
+        }
+    }
+
+    impl fmt::Display for Value {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Value::Integer(n) => write!(f, "{}", n),
+                Value::BigInteger(n) => write!(f, "{}", n),
+                // Debug formatting keeps the trailing `.0` on whole numbers
+                // so floats can be told apart from integers
+                Value::Number(n) => write!(f, "{:?}", n),
+                Value::String(s) => write!(f, "{}", s),
+                Value::Boolean(b) => write!(f, "{}", b),
+                Value::Proc(p) => write!(f, "<proc {}>", p.name),
+                Value::Nothing => write!(f, "nothing"),
+            }
+        }
+    }
+}
+
+pub mod env {
+    use std::{cell::RefCell, collections::HashMap, rc::Rc};
+    use crate::ast::node::Type;
+    use super::value::Value;
+
+    // A variable's current value and the type it was declared with
+    #[derive(Debug, Clone)]
+    pub struct Binding {
+        pub value: Value,
+        pub typ: Type,
+    }
+
+    // A single level of scope holding the variables declared in it,
+    // chained to the scope that encloses it
+    #[derive(Debug, Default)]
+    pub struct Scope {
+        values: HashMap<String, Binding>,
+        parent: Option<Rc<RefCell<Scope>>>,
+    }
+
+    impl Scope {
+        // Looks for a variable in this scope, then each enclosing scope
+        fn get(&self, name: &str) -> Option<Binding> {
+            match self.values.get(name) {
+                Some(binding) => Some(binding.clone()),
+                None => self.parent.as_ref()?.borrow().get(name),
+            }
+        }
+
+        // Updates a variable in the scope it was declared in
+        fn set(&mut self, name: &str, value: Value) -> bool {
+            match self.values.get_mut(name) {
+                Some(binding) => {
+                    binding.value = value;
+                    true
+                },
+                None => match &self.parent {
+                    Some(parent) => parent.borrow_mut().set(name, value),
+                    None => false,
+                },
+            }
+        }
+    }
+
+    // Resolves variables through the chain of scopes, starting from the innermost
+    // A variable may shadow one from an enclosing scope, but can't be declared
+    // twice in the same scope
+    #[derive(Debug)]
+    pub struct Environment {
+        current: Rc<RefCell<Scope>>,
+    }
+
+    impl Environment {
+        // Creates an environment with only the global scope
+        pub fn new() -> Self {
+            Self { current: Rc::new(RefCell::new(Scope::default())) }
+        }
+
+        // Enters a new scope nested inside the current one
+        pub fn push(&mut self) {
+            let parent = Rc::clone(&self.current);
+            let scope = Scope { values: HashMap::new(), parent: Some(parent) };
+            self.current = Rc::new(RefCell::new(scope));
+        }
+
+        // Leaves the current scope, dropping every variable declared in it
+        pub fn pop(&mut self) {
+            let parent = self.current.borrow().parent.clone();
+            if let Some(parent) = parent {
+                self.current = parent;
+            }
+        }
+
+        // Returns the current scope so procedures can capture it
+        pub fn capture(&self) -> Rc<RefCell<Scope>> {
+            Rc::clone(&self.current)
+        }
+
+        // Enters a call frame nested inside a procedure's captured scope,
+        // returning the caller's scope to be restored once the call is done
+        pub fn call_frame(&mut self, closure: Rc<RefCell<Scope>>) -> Rc<RefCell<Scope>> {
+            let scope = Scope { values: HashMap::new(), parent: Some(closure) };
+            std::mem::replace(&mut self.current, Rc::new(RefCell::new(scope)))
+        }
+
+        // Returns to the caller's scope after a call
+        pub fn restore(&mut self, scope: Rc<RefCell<Scope>>) {
+            self.current = scope;
+        }
+
+        // Declares a new variable in the current scope, returning false if
+        // it was already declared in this scope
+        pub fn declare(&mut self, name: &str, binding: Binding) -> bool {
+            let mut scope = self.current.borrow_mut();
+            if scope.values.contains_key(name) {
+                return false;
+            }
+            scope.values.insert(name.to_string(), binding);
+            true
+        }
+
+        pub fn get(&self, name: &str) -> Option<Binding> {
+            self.current.borrow().get(name)
+        }
+
+        // Rebinds the innermost variable with the given name, returning
+        // false if it isn't declared in any enclosing scope
+        pub fn set(&mut self, name: &str, value: Value) -> bool {
+            self.current.borrow_mut().set(name, value)
+        }
+    }
+
+    impl Default for Environment {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+}
+
+pub mod eval {
+    use std::{io::Write, rc::Rc};
+    use num_bigint::BigInt;
+    use num_traits::{FromPrimitive, ToPrimitive, Zero};
+    use crate::ast::{ast::{Ast, NodeKey}, node::{Assign, BinaryExpr, BinaryOp, Call, Cast, For, If, LiteralType, Node, ProcDecl, Type, UnaryExpr, UnaryOp, VarDecl, While}};
+    use crate::error::error::{self, Diagnostic, Span};
+    use super::env::{Binding, Environment};
+    use super::value::{Procedure, Value};
+
+    // Reasons evaluation of a node stopped early
+    pub enum Flow {
+        Error(Diagnostic),
+        // `return` unwinding to the procedure call it is inside of
+        Return(Value),
+        // `break` and `continue` unwinding to the loop they are inside of
+        Break,
+        Continue,
+    }
+
+    impl From<Diagnostic> for Flow {
+        fn from(diagnostic: Diagnostic) -> Self {
+            Flow::Error(diagnostic)
+        }
+    }
+
+    type EvalRes = Result<Value, Flow>;
+
+    // How many procedure calls can be nested before giving up, so runaway
+    // recursion is reported instead of overflowing the native stack
+    const MAX_CALL_DEPTH: usize = 1000;
+
+    // Walks the tree from `ast.root` and evaluates each top level expression
+    // Anything the script prints is written to `out`, which is stdout when
+    // running a file but can be any writer, e.g. a `Vec<u8>` to capture output
+    pub struct Evaluator<'a> {
+        ast: &'a Ast,
+        env: Environment,
+        out: &'a mut dyn Write,
+
+        // How many procedure calls are currently being evaluated
+        depth: usize,
+    }
+
+    impl<'a> Evaluator<'a> {
+        pub fn new(ast: &'a Ast, out: &'a mut dyn Write) -> Self {
+            Self::resume(ast, Environment::new(), out)
+        }
+
+        // Carries on evaluating with the variables of an earlier evaluator
+        pub fn resume(ast: &'a Ast, env: Environment, out: &'a mut dyn Write) -> Self {
+            Self { ast, env, out, depth: 0 }
+        }
+
+        pub fn into_env(self) -> Environment {
+            self.env
+        }
+
+        // Evaluates every statement owned by the program node in order and
+        // prints the result of each top level expression, stopping at the
+        // first runtime error
+        pub fn run(&mut self) -> Result<(), Diagnostic> {
+            let body = match &self.ast.tree[self.ast.root] {
+                Node::Program(body) => body,
+                _ => panic!("Root of the tree is not a program node"),
+            };
+
+            self.run_statements(body)
+        }
+
+        pub fn run_statements(&mut self, body: &[NodeKey]) -> Result<(), Diagnostic> {
+            for key in body {
+                let value = match self.eval_node(*key) {
+                    Ok(value) => value,
+                    Err(Flow::Error(diagnostic)) => return Err(diagnostic),
+                    // The parser only allows these inside procedures and loops
+                    Err(Flow::Return(_)) => panic!("Return escaped a procedure"),
+                    Err(Flow::Break | Flow::Continue) => panic!("Loop control escaped a loop"),
+                };
+
+                if value != Value::Nothing {
+                    let span = self.ast.span(*key);
+                    self.write_line(&value.to_string(), span)?;
+                }
+            }
+
+            Ok(())
+        }
+
+        fn eval_node(&mut self, key: NodeKey) -> EvalRes {
+            match &self.ast.tree[key] {
+                Node::BinaryExpr(expr) => self.eval_binary(expr, self.ast.span(key)),
+                Node::UnaryExpr(expr) => self.eval_unary(expr, self.ast.span(key)),
+                Node::Grouping(inner) => self.eval_node(*inner),
+
+                // Literals
+                Node::StringLitExpr(lit) | Node::NumberLitExpr(lit) | Node::IntegerLitExpr(lit) | Node::BooleanLitExpr(lit) => {
+                    Ok(match &lit.typ {
+                        LiteralType::String(s) => Value::String(s.clone()),
+                        LiteralType::Number(n) => Value::Number(*n),
+                        LiteralType::Integer(n) => Value::Integer(*n),
+                        LiteralType::BigInteger(n) => Value::BigInteger(n.clone()),
+                        LiteralType::Boolean(b) => Value::Boolean(*b),
+                    })
+                },
+
+                Node::Concat(parts) => {
+                    let mut string = String::new();
+                    for part in parts {
+                        string.push_str(&self.eval_node(*part)?.to_string());
+                    }
+                    Ok(Value::String(string))
+                },
+
+                Node::Cast(cast) => self.eval_cast(cast, self.ast.span(key)),
+
+                // Variables
+                Node::Ident(name) => match self.env.get(name) {
+                    Some(binding) => Ok(binding.value),
+                    None => Err(Diagnostic::error(
+                        error::UNDEFINED_VARIABLE,
+                        format!("Variable `{}` has not been declared", name),
+                        self.ast.span(key),
+                    ).into()),
+                },
+                Node::VarDecl(decl) => self.eval_var_decl(decl, self.ast.span(key)),
+                Node::Assign(assign) => self.eval_assign(assign, self.ast.span(key)),
+
+                // Procedures
+                Node::ProcDecl(decl) => self.eval_proc_decl(decl),
+                Node::Call(call) => self.eval_call(call, self.ast.span(key)),
+                Node::Return(value) => {
+                    let value = match value {
+                        Some(value) => self.eval_node(*value)?,
+                        None => Value::Nothing,
+                    };
+                    Err(Flow::Return(value))
+                },
+
+                Node::Print(values) => self.eval_print(values, self.ast.span(key)),
+
+                Node::If(stmt) => self.eval_if(stmt),
+
+                Node::While(stmt) => self.eval_while(stmt),
+                Node::For(stmt) => self.eval_for(stmt),
+                Node::Break => Err(Flow::Break),
+                Node::Continue => Err(Flow::Continue),
+
+                Node::Block(body) => self.eval_block(body),
+
+                Node::Program(_) => panic!("Cannot evaluate a nested program node"),
+            }
+        }
+
+        // Declares a variable with the value converted to it's annotated type,
+        // or the value's own type if there is no annotation
+        fn eval_var_decl(&mut self, decl: &VarDecl, span: Span) -> EvalRes {
+            let value = self.eval_node(decl.value)?;
+            let (value, typ) = match (decl.typ, value.typ()) {
+                (Some(typ), _) => (coerce(value, typ).map_err(|(code, message)| Diagnostic::error(code, message, span))?, typ),
+                (None, Some(typ)) => (value, typ),
+                (None, None) => return Err(Diagnostic::error(
+                    error::TYPE_MISMATCH,
+                    format!("Cannot store nothing in `{}`", decl.name),
+                    span,
+                ).into()),
+            };
+
+            if !self.env.declare(&decl.name, Binding { value, typ }) {
+                return Err(Diagnostic::error(
+                    error::ALREADY_DECLARED,
+                    format!("Variable `{}` is already declared in this scope, use `{} -> value` to reassign it", decl.name, decl.name),
+                    decl.name_span,
+                ).into());
+            }
+
+            Ok(Value::Nothing)
+        }
+
+        // Rebinds an already declared variable, converting the value to the
+        // type it was declared with
+        fn eval_assign(&mut self, assign: &Assign, span: Span) -> EvalRes {
+            let value = self.eval_node(assign.value)?;
+            let binding = match self.env.get(&assign.name) {
+                Some(binding) => binding,
+                None => return Err(Diagnostic::error(
+                    error::UNDEFINED_VARIABLE,
+                    format!("Variable `{}` has not been declared, use `{} = value` to declare it", assign.name, assign.name),
+                    assign.name_span,
+                ).into()),
+            };
+
+            let value = coerce(value, binding.typ).map_err(|(code, message)| Diagnostic::error(
+                code,
+                format!("Cannot reassign `{}`: {}", assign.name, message),
+                span,
+            ))?;
+            self.env.set(&assign.name, value);
+
+            Ok(Value::Nothing)
+        }
+
+        // Declares a procedure which captures the current scope
+        fn eval_proc_decl(&mut self, decl: &ProcDecl) -> EvalRes {
+            let procedure = Procedure {
+                name: decl.name.clone(),
+                params: decl.params.clone(),
+                returns: decl.returns,
+                body: decl.body,
+                closure: self.env.capture(),
+            };
+
+            let binding = Binding { value: Value::Proc(Rc::new(procedure)), typ: Type::Procedure };
+            if !self.env.declare(&decl.name, binding) {
+                return Err(Diagnostic::error(
+                    error::ALREADY_DECLARED,
+                    format!("`{}` is already declared in this scope", decl.name),
+                    decl.name_span,
+                ).into());
+            }
+
+            Ok(Value::Nothing)
+        }
+
+        // Calls a procedure, binding each argument to it's parameter in a new
+        // call frame and checking the returned value against the return type
+        fn eval_call(&mut self, call: &Call, span: Span) -> EvalRes {
+            let procedure = match self.eval_node(call.callee)? {
+                Value::Proc(procedure) => procedure,
+                value => return Err(Diagnostic::error(
+                    error::NOT_CALLABLE,
+                    format!("Cannot call a value of type {}", value.type_name()),
+                    self.ast.span(call.callee),
+                ).into()),
+            };
+
+            if call.args.len() != procedure.params.len() {
+                return Err(Diagnostic::error(
+                    error::ARGUMENT_COUNT,
+                    format!(
+                        "`{}` takes {} argument(s) but {} were given",
+                        procedure.name, procedure.params.len(), call.args.len()
+                    ),
+                    span,
+                ).into());
+            }
+
+            // Evaluate arguments in the caller's scope
+            let mut args = Vec::<Value>::new();
+            for (arg, param) in call.args.iter().zip(&procedure.params) {
+                let value = self.eval_node(*arg)?;
+                let arg_span = self.ast.span(*arg);
+                let value = coerce(value, param.typ).map_err(|(code, message)| Diagnostic::error(
+                    code,
+                    format!("Invalid argument for `{}`: {}", param.name, message),
+                    arg_span,
+                ))?;
+                args.push(value);
+            }
+
+            if self.depth == MAX_CALL_DEPTH {
+                return Err(Diagnostic::error(
+                    error::CALL_DEPTH,
+                    format!(
+                        "Calls are nested more than {} deep when calling `{}`, does a recursive procedure never stop?",
+                        MAX_CALL_DEPTH, procedure.name
+                    ),
+                    span,
+                ).into());
+            }
+
+            // Run the body in a frame nested in the procedure's own scope
+            let caller = self.env.call_frame(Rc::clone(&procedure.closure));
+            for (param, value) in procedure.params.iter().zip(args) {
+                self.env.declare(&param.name, Binding { value, typ: param.typ });
+            }
+            self.depth += 1;
+            let result = self.eval_node(procedure.body);
+            self.depth -= 1;
+            self.env.restore(caller);
+
+            let value = match result {
+                Ok(_) => Value::Nothing,
+                Err(Flow::Return(value)) => value,
+                Err(flow) => return Err(flow),
+            };
+
+            match (procedure.returns, value) {
+                (None, Value::Nothing) => Ok(Value::Nothing),
+                (None, value) => Err(Diagnostic::error(
+                    error::TYPE_MISMATCH,
+                    format!("`{}` has no return type but returned a value of type {}", procedure.name, value.type_name()),
+                    span,
+                ).into()),
+                (Some(typ), Value::Nothing) => Err(Diagnostic::error(
+                    error::MISSING_RETURN,
+                    format!("`{}` should return a value of type {} but did not return a value", procedure.name, typ),
+                    span,
+                ).into()),
+                (Some(typ), value) => coerce(value, typ).map_err(|(code, message)| Diagnostic::error(
+                    code,
+                    format!("Invalid return value from `{}`: {}", procedure.name, message),
+                    span,
+                ).into()),
+            }
+        }
+
+        // Prints each value separated by a space, followed by a newline
+        fn eval_print(&mut self, values: &[NodeKey], span: Span) -> EvalRes {
+            let mut line = Vec::<String>::new();
+            for key in values {
+                line.push(self.eval_node(*key)?.to_string());
+            }
+
+            self.write_line(&line.join(" "), span)?;
+            Ok(Value::Nothing)
+        }
+
+        // Runs the first branch whose condition is truthy
+        fn eval_if(&mut self, stmt: &If) -> EvalRes {
+            if self.eval_node(stmt.cond)?.is_truthy() {
+                self.eval_node(stmt.then)
+            } else if let Some(otherwise) = stmt.otherwise {
+                self.eval_node(otherwise)
+            } else {
+                Ok(Value::Nothing)
+            }
+        }
+
+        // Runs the body for as long as the condition is truthy
+        fn eval_while(&mut self, stmt: &While) -> EvalRes {
+            while self.eval_node(stmt.cond)?.is_truthy() {
+                match self.eval_node(stmt.body) {
+                    Ok(_) | Err(Flow::Continue) => {},
+                    Err(Flow::Break) => break,
+                    Err(flow) => return Err(flow),
+                }
+            }
+
+            Ok(Value::Nothing)
+        }
+
+        // Runs the body once for each integer from start up to but not including end,
+        // with the loop variable declared in a scope of it's own each time around
+        fn eval_for(&mut self, stmt: &For) -> EvalRes {
+            let start = self.eval_range_bound(stmt.start)?;
+            let end = self.eval_range_bound(stmt.end)?;
+
+            for i in start..end {
+                self.env.push();
+                self.env.declare(&stmt.var, Binding { value: Value::Integer(i), typ: Type::Integer });
+                let result = self.eval_node(stmt.body);
+                self.env.pop();
+
+                match result {
+                    Ok(_) | Err(Flow::Continue) => {},
+                    Err(Flow::Break) => break,
+                    Err(flow) => return Err(flow),
+                }
+            }
+
+            Ok(Value::Nothing)
+        }
+
+        // Evaluates the start or end of a range, which must be an integer
+        fn eval_range_bound(&mut self, key: NodeKey) -> Result<i64, Flow> {
+            match self.eval_node(key)? {
+                Value::Integer(n) => Ok(n),
+                Value::BigInteger(n) => Err(Diagnostic::error(
+                    error::INTEGER_OVERFLOW,
+                    format!("{} is too large to be used in a range", n),
+                    self.ast.span(key),
+                ).into()),
+                value => Err(Diagnostic::error(
+                    error::TYPE_MISMATCH,
+                    format!("Ranges must be made of integers but found {}", value.type_name()),
+                    self.ast.span(key),
+                ).into()),
+            }
+        }
+
+        // Writes a line to the output sink
+        fn write_line(&mut self, line: &str, span: Span) -> Result<(), Diagnostic> {
+            writeln!(self.out, "{}", line).map_err(|e| Diagnostic::error(
+                error::OUTPUT_FAILED,
+                format!("Failed to write output: {}", e),
+                span,
+            ))
+        }
+
+        // Runs each statement of a block inside a new scope, which is
+        // left again even if a statement fails
+        fn eval_block(&mut self, body: &[NodeKey]) -> EvalRes {
+            self.env.push();
+            let result = body.iter().try_for_each(|key| self.eval_node(*key).map(|_| ()));
+            self.env.pop();
+
+            result.map(|_| Value::Nothing)
+        }
+
+        fn eval_binary(&mut self, expr: &BinaryExpr, span: Span) -> EvalRes {
+            let lv = self.eval_node(expr.ln)?;
+
+            // `and` and `or` short circuit without evaluating the right side
+            match (&expr.op, lv.is_truthy()) {
+                (BinaryOp::And, false) => return Ok(Value::Boolean(false)),
+                (BinaryOp::Or, true) => return Ok(Value::Boolean(true)),
+                _ => {},
+            }
+
+            let rv = self.eval_node(expr.rn)?;
+            binary(&expr.op, lv, rv).map_err(|(code, message)| Diagnostic::error(code, message, span).into())
+        }
+
+        fn eval_cast(&mut self, cast: &Cast, span: Span) -> EvalRes {
+            let value = self.eval_node(cast.value)?;
+            convert(value, cast.typ).map_err(|(code, message)| Diagnostic::error(code, message, span).into())
+        }
+
+        fn eval_unary(&mut self, expr: &UnaryExpr, span: Span) -> EvalRes {
+            let rv = self.eval_node(expr.rn)?;
+            unary(&expr.op, rv).map_err(|(code, message)| Diagnostic::error(code, message, span).into())
+        }
+    }
+
+    // Error code and message, given a span by the caller
+    type RuntimeError = (&'static str, String);
+
+    // Converts a value to be stored in a variable of the given type
+    // Integers are promoted to numbers, anything else must match exactly
+    // Promotion rounds integers past 2^53 to the nearest number, see `promote`
+    //
+    // | value \ type | integer | number   | string | boolean |
+    // |--------------|---------|----------|--------|---------|
+    // | integer      | kept    | promoted | error  | error   |
+    // | number       | error   | kept     | error  | error   |
+    // | string       | error   | error    | kept   | error   |
+    // | boolean      | error   | error    | error  | kept    |
+    //
+    // Anything else needs an explicit conversion, see `convert`
+    fn coerce(value: Value, typ: Type) -> Result<Value, RuntimeError> {
+        match (value, typ) {
+            (value @ (Value::Integer(_) | Value::BigInteger(_)), Type::Number) => promote(&value),
+            (value, typ) if value.typ() == Some(typ) => Ok(value),
+            (value, typ) => Err((error::TYPE_MISMATCH, format!(
+                "Expected a value of type {} but found {}", typ, value.type_name()
+            ))),
+        }
+    }
+
+    // Turns an integer into the nearest number, which loses precision past
+    // 2^53 and is an error for integers too large to be a number at all
+    fn promote(value: &Value) -> Result<Value, RuntimeError> {
+        match value.as_number() {
+            Some(n) if n.is_finite() => Ok(Value::Number(n)),
+            _ => {
+                let digits = value.to_string().trim_start_matches('-').len();
+                Err((error::INVALID_CONVERSION, format!("An integer with {} digits is too large to be a number", digits)))
+            },
+        }
+    }
+
+    // Explicitly converts a value with `integer()`, `number()`, `string()`
+    // or `boolean()`
+    // - numbers become integers by dropping the fraction, so `integer(3.9)` is 3
+    // - strings are parsed, ignoring surrounding whitespace, and are an error
+    //   if they don't hold a value of the type, e.g. `integer("3.5")`
+    // - booleans become 1 or 0
+    // - anything but nothing can become a string, written as `print` would
+    // - anything can become a boolean based on it's truthiness
+    fn convert(value: Value, typ: Type) -> Result<Value, RuntimeError> {
+        let failed = |value: &Value| {
+            let what = match value {
+                Value::String(s) => format!("\"{}\"", s),
+                Value::Number(n) => n.to_string(),
+                Value::Nothing => String::from("nothing"),
+                value => format!("a {}", value.type_name()),
+            };
+            (error::INVALID_CONVERSION, format!("Cannot convert {} to {}", what, typ))
+        };
+
+        match (value, typ) {
+            (Value::Nothing, _) => Err(failed(&Value::Nothing)),
+            (value, Type::Boolean) => Ok(Value::Boolean(value.is_truthy())),
+            (value, Type::String) => Ok(Value::String(value.to_string())),
+
+            (value @ (Value::Integer(_) | Value::BigInteger(_)), Type::Integer) => Ok(value),
+            (Value::Number(n), Type::Integer) if n.is_finite() => {
+                Ok(Value::from(BigInt::from_f64(n.trunc()).unwrap_or_default()))
+            },
+            (Value::String(s), Type::Integer) => match s.trim().parse::<BigInt>() {
+                Ok(n) => Ok(Value::from(n)),
+                Err(_) => Err(failed(&Value::String(s))),
+            },
+            (Value::Boolean(b), Type::Integer) => Ok(Value::Integer(b as i64)),
+
+            (value @ (Value::Integer(_) | Value::BigInteger(_)), Type::Number) => promote(&value),
+            (value @ Value::Number(_), Type::Number) => Ok(value),
+            (Value::String(s), Type::Number) => match s.trim().parse::<f64>() {
+                Ok(n) if n.is_finite() => Ok(Value::Number(n)),
+                _ => Err(failed(&Value::String(s))),
+            },
+            (Value::Boolean(b), Type::Number) => Ok(Value::Number(b as i64 as f64)),
+
+            (value, _) => Err(failed(&value)),
+        }
+    }
+
+    // Applies a unary operator to a value
+    // Only integers and numbers can be negated, while anything can be
+    // negated logically based on it's truthiness
+    fn unary(op: &UnaryOp, rv: Value) -> Result<Value, RuntimeError> {
+        match (op, rv) {
+            (UnaryOp::Negate, Value::Integer(n)) => match n.checked_neg() {
+                Some(n) => Ok(Value::Integer(n)),
+                None => Ok(Value::from(-BigInt::from(n))),
+            },
+            (UnaryOp::Negate, Value::BigInteger(n)) => Ok(Value::from(-n)),
+            (UnaryOp::Negate, Value::Number(n)) => Ok(Value::Number(-n)),
+            (UnaryOp::Negate, rv) => Err((error::TYPE_MISMATCH, format!("Cannot negate a {}", rv.type_name()))),
+            (UnaryOp::Not, rv) => Ok(Value::Boolean(!rv.is_truthy())),
+        }
+    }
+
+    // Applies a binary operator to two values
+    fn binary(op: &BinaryOp, lv: Value, rv: Value) -> Result<Value, RuntimeError> {
+        match op {
+            BinaryOp::Equal => Ok(Value::Boolean(equal(&lv, &rv))),
+            BinaryOp::NotEqual => Ok(Value::Boolean(!equal(&lv, &rv))),
+            BinaryOp::Less | BinaryOp::LessEqual | BinaryOp::Greater | BinaryOp::GreaterEqual => compare(op, lv, rv),
+            BinaryOp::And => Ok(Value::Boolean(lv.is_truthy() && rv.is_truthy())),
+            BinaryOp::Or => Ok(Value::Boolean(lv.is_truthy() || rv.is_truthy())),
+            _ => arithmetic(op, lv, rv),
+        }
+    }
+
+    // Values of different types are never equal, except for integers
+    // and numbers which are compared by value
+    fn equal(lv: &Value, rv: &Value) -> bool {
+        match (lv, rv) {
+            (Value::Number(_), Value::Integer(_) | Value::BigInteger(_))
+            | (Value::Integer(_) | Value::BigInteger(_), Value::Number(_)) => lv.as_number() == rv.as_number(),
+            (lv, rv) => lv == rv,
+        }
+    }
+
+    // Orders integers and numbers by value and strings alphabetically
+    fn compare(op: &BinaryOp, lv: Value, rv: Value) -> Result<Value, RuntimeError> {
+        let ordering = match (&lv, &rv) {
+            (Value::Integer(l), Value::Integer(r)) => l.partial_cmp(r),
+            (Value::String(l), Value::String(r)) => l.partial_cmp(r),
+            _ => match (lv.as_big_integer(), rv.as_big_integer(), lv.as_number(), rv.as_number()) {
+                (Some(l), Some(r), _, _) => l.partial_cmp(&r),
+                (_, _, Some(l), Some(r)) => l.partial_cmp(&r),
+                _ => return Err((error::TYPE_MISMATCH, format!(
+                    "Cannot compare {} and {} with `{}`", lv.type_name(), rv.type_name(), op
+                ))),
+            },
+        };
+
+        // NaN compares false against everything
+        let result = match ordering {
+            Some(ordering) => match op {
+                BinaryOp::Less => ordering.is_lt(),
+                BinaryOp::LessEqual => ordering.is_le(),
+                BinaryOp::Greater => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            },
+            None => false,
+        };
+
+        Ok(Value::Boolean(result))
+    }
+
+    // Applies an arithmetic operator to two values
+    //
+    // Promotion rules:
+    // - integer (op) integer stays an integer, except for `/` which only
+    //   stays an integer if the division is exact and `^` with a negative
+    //   exponent, both of which produce a number
+    // - integers are 64 bit and grow into bignums rather than overflowing,
+    //   shrinking back once the result fits again
+    // - integer (op) number promotes the integer to a number
+    // - string + string concatenates, any other use of a string is an error
+    fn arithmetic(op: &BinaryOp, lv: Value, rv: Value) -> Result<Value, RuntimeError> {
+        match (lv, rv) {
+            (Value::Integer(l), Value::Integer(r)) => integer_binary(op, l, r),
+            (Value::String(l), Value::String(r)) if *op == BinaryOp::Plus => Ok(Value::String(l + &r)),
+            (lv, rv) => match (lv.as_big_integer(), rv.as_big_integer(), lv.as_number(), rv.as_number()) {
+                (Some(l), Some(r), _, _) => big_integer_binary(op, l, r),
+                (_, _, Some(l), Some(r)) => number_binary(op, l, r),
+                _ => Err((error::TYPE_MISMATCH, format!(
+                    "Cannot apply `{}` to {} and {}", op, lv.type_name(), rv.type_name()
+                ))),
+            },
+        }
+    }
+
+    // 64 bit integer arithmetic, redone with bignums if it overflows
+    fn integer_binary(op: &BinaryOp, l: i64, r: i64) -> Result<Value, RuntimeError> {
+        let result = match op {
+            BinaryOp::Plus => l.checked_add(r),
+            BinaryOp::Minus => l.checked_sub(r),
+            BinaryOp::Multiply => l.checked_mul(r),
+            BinaryOp::Divide => {
+                if r == 0 {
+                    return Err((error::DIVIDE_BY_ZERO, String::from("Division by zero")));
+                }
+                if l.checked_rem(r).is_some_and(|rem| rem != 0) {
+                    return Ok(Value::Number(l as f64 / r as f64));
+                }
+                l.checked_div(r)
+            },
+            BinaryOp::Modulus => {
+                if r == 0 {
+                    return Err((error::DIVIDE_BY_ZERO, String::from("Modulus by zero")));
+                }
+                l.checked_rem(r)
+            },
+            BinaryOp::Power => return integer_power(l, r),
+            _ => unreachable!("`{}` is not an arithmetic operator", op),
+        };
+
+        match result {
+            Some(n) => Ok(Value::Integer(n)),
+            None => big_integer_binary(op, BigInt::from(l), BigInt::from(r)),
+        }
+    }
+
+    fn big_integer_binary(op: &BinaryOp, l: BigInt, r: BigInt) -> Result<Value, RuntimeError> {
+        let result = match op {
+            BinaryOp::Plus => l + r,
+            BinaryOp::Minus => l - r,
+            BinaryOp::Multiply => l * r,
+            BinaryOp::Divide => {
+                if r.is_zero() {
+                    return Err((error::DIVIDE_BY_ZERO, String::from("Division by zero")));
+                }
+                if !(&l % &r).is_zero() {
+                    return Ok(Value::Number(big_to_f64(&l) / big_to_f64(&r)));
+                }
+                l / r
+            },
+            BinaryOp::Modulus => {
+                if r.is_zero() {
+                    return Err((error::DIVIDE_BY_ZERO, String::from("Modulus by zero")));
+                }
+                l % r
+            },
+            BinaryOp::Power => return big_integer_power(l, r),
+            _ => unreachable!("`{}` is not an arithmetic operator", op),
+        };
+
+        Ok(Value::from(result))
+    }
+
+    // Raises an integer to an integer power, falling back to a number for
+    // negative exponents since the result is a fraction
+    // Bases whose powers never grow are answered straight away, so something
+    // like `1 ^ 1000000000` doesn't overflow or loop, and powers of two are shifts
+    fn integer_power(l: i64, r: i64) -> Result<Value, RuntimeError> {
+        if r < 0 {
+            return number_power(l as f64, r as f64).map(Value::Number);
+        }
+
+        let result = match (l, r) {
+            (_, 0) => Some(1),
+            (_, 1) | (0, _) | (1, _) => Some(l),
+            (-1, _) => Some(if r % 2 == 0 { 1 } else { -1 }),
+            (2, _) if r < 63 => Some(1 << r),
+            // Exponentiation by squaring
+            _ => u32::try_from(r).ok().and_then(|r| l.checked_pow(r)),
+        };
+
+        match result {
+            Some(n) => Ok(Value::Integer(n)),
+            None => big_integer_power(BigInt::from(l), BigInt::from(r)),
+        }
+    }
+
+    // Largest bignum `^` will produce, about 300,000 digits
+    const MAX_POWER_BITS: u64 = 1 << 20;
+
+    fn big_integer_power(l: BigInt, r: BigInt) -> Result<Value, RuntimeError> {
+        if r < BigInt::zero() {
+            return number_power(big_to_f64(&l), big_to_f64(&r)).map(Value::Number);
+        }
+
+        // Powers of 0, 1 and -1 never grow, however large the exponent
+        match l.to_i64() {
+            Some(0 | 1) => return Ok(Value::from(l)),
+            Some(-1) => return Ok(Value::Integer(if r.bit(0) { -1 } else { 1 })),
+            _ => {},
+        }
+
+        let too_large = || (error::INTEGER_OVERFLOW, format!("Result of {} ^ {} is too large", l, r));
+        let exponent = match r.to_u32() {
+            Some(exponent) if l.bits().saturating_mul(exponent as u64) <= MAX_POWER_BITS => exponent,
+            _ => return Err(too_large()),
+        };
+
+        Ok(Value::from(l.pow(exponent)))
+    }
+
+    // Whole exponents use repeated multiplication, which is faster and exact
+    // for small powers, anything else falls back to `powf`
+    // Results that aren't a finite number are errors, as with division by zero
+    fn number_power(l: f64, r: f64) -> Result<f64, RuntimeError> {
+        let result = if r.fract() == 0.0 && r.abs() <= i32::MAX as f64 {
+            l.powi(r as i32)
+        } else {
+            l.powf(r)
+        };
+
+        if result.is_finite() {
+            Ok(result)
+        } else if l == 0.0 && r < 0.0 {
+            Err((error::DIVIDE_BY_ZERO, String::from("Division by zero, 0 cannot be raised to a negative power")))
+        } else if result.is_nan() {
+            Err((error::INVALID_POWER, format!("Cannot raise {:?} to the power of {:?}, the result is not a real number", l, r)))
+        } else {
+            Err((error::INVALID_POWER, format!("Result of {:?} ^ {:?} is too large to be a number", l, r)))
+        }
+    }
+
+    fn big_to_f64(n: &BigInt) -> f64 {
+        n.to_f64().unwrap_or(f64::NAN)
+    }
+
+    fn number_binary(op: &BinaryOp, l: f64, r: f64) -> Result<Value, RuntimeError> {
+        let result = match op {
+            BinaryOp::Plus => l + r,
+            BinaryOp::Minus => l - r,
+            BinaryOp::Multiply => l * r,
+            BinaryOp::Divide => {
+                if r == 0.0 {
+                    return Err((error::DIVIDE_BY_ZERO, String::from("Division by zero")));
+                }
+                l / r
+            },
+            BinaryOp::Modulus => {
+                if r == 0.0 {
+                    return Err((error::DIVIDE_BY_ZERO, String::from("Modulus by zero")));
+                }
+                l % r
+            },
+            BinaryOp::Power => number_power(l, r)?,
+            _ => unreachable!("`{}` is not an arithmetic operator", op),
+        };
+
+        Ok(Value::Number(result))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use num_bigint::BigInt;
+        use crate::ast::{ast::Ast, node::BinaryOp};
+        use crate::error::error;
+        use super::{big_integer_binary, integer_binary, integer_power, number_power, promote, Evaluator, Value};
+
+        // Lexes, parses and runs `source`, returning everything it printed
+        fn run(source: &str) -> String {
+            let ast = Ast::from_source(source);
+            let mut out = Vec::<u8>::new();
+            Evaluator::new(&ast, &mut out).run().expect("source should run");
+            String::from_utf8(out).expect("output should be UTF-8")
+        }
+
+        #[test]
+        fn prints_integers_and_numbers_differently() {
+            assert_eq!(run("print 6 / 2\nprint 7 / 2\nprint 1.0\nprint 2 * 1.5\n"), "3\n3.5\n1.0\n3.0\n");
+        }
+
+        #[test]
+        fn prints_strings_without_quotes() {
+            assert_eq!(run("name = \"darcy\"\nprint \"hi {name}\"\n"), "hi darcy\n");
+        }
+
+        #[test]
+        fn prints_values_separated_by_spaces() {
+            assert_eq!(run("print 1, \"two\", 3.0, true\nprint\n"), "1 two 3.0 true\n\n");
+        }
+
+        #[test]
+        fn prints_top_level_expressions() {
+            assert_eq!(run("x = 2\nx * 3\n"), "6\n");
+        }
+
+        #[test]
+        fn integer_overflow_becomes_big_integer() {
+            let max = BigInt::from(i64::MAX);
+            assert_eq!(integer_binary(&BinaryOp::Plus, i64::MAX, 1), Ok(Value::BigInteger(&max + 1)));
+            assert_eq!(integer_binary(&BinaryOp::Multiply, i64::MIN, -1), Ok(Value::BigInteger(-BigInt::from(i64::MIN))));
+            assert_eq!(integer_binary(&BinaryOp::Plus, 1, 2), Ok(Value::Integer(3)));
+        }
+
+        #[test]
+        fn big_integer_back_in_range_becomes_integer() {
+            let max = BigInt::from(i64::MAX);
+            assert_eq!(big_integer_binary(&BinaryOp::Minus, &max + 1, BigInt::from(1)), Ok(Value::Integer(i64::MAX)));
+            assert_eq!(run("x = 9223372036854775807 + 1\nprint x\nprint x - 1\n"), "9223372036854775808\n9223372036854775807\n");
+        }
+
+        #[test]
+        fn powers_that_are_not_finite_are_errors() {
+            assert_eq!(integer_power(2, -2), Ok(Value::Number(0.25)));
+            assert_eq!(integer_power(0, -1).unwrap_err().0, error::DIVIDE_BY_ZERO);
+            assert_eq!(number_power(-8.0, 0.5).unwrap_err().0, error::INVALID_POWER);
+            assert_eq!(number_power(10.0, 400.0).unwrap_err().0, error::INVALID_POWER);
+        }
+
+        #[test]
+        fn promotion_rounds_large_integers() {
+            assert_eq!(run("x: number = 123456789012345678901\nprint x\n"), "1.2345678901234568e20\n");
+            let too_large = Value::BigInteger(BigInt::from(10).pow(400));
+            assert_eq!(promote(&too_large).unwrap_err().0, error::INVALID_CONVERSION);
+        }
+    }
+}
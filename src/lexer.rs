@@ -1,6 +1,7 @@
 pub mod lexer {
     use std::iter::Peekable;
     use crate::{Token, TokenType};
+    use crate::error::error::{self, Diagnostic, Span};
 
     // Lexer result handles interfacing between self.scan() and
     // the various helper functions it calls
@@ -10,33 +11,71 @@ pub mod lexer {
         End, // Found end of file
     }
 
-    pub struct Lexer<Iter: Iterator<Item = char>> {
+    pub struct Lexer<Iter: Iterator<Item = char> + Clone> {
         pub tokens: Vec<Token>,
         chars: Peekable<Iter>,
         current: char,
+
+        // Position of current, and of the next char to be read
+        pos: usize,
         line: usize,
+        col: usize,
+        next_pos: usize,
+        next_line: usize,
+        next_col: usize,
+
+        // Where the token currently being scanned started
+        start: Span,
+
         pub source: Vec<String>,
+        diagnostics: Vec<Diagnostic>,
+
+        // Whether comments are pushed as tokens for tools like formatters
+        keep_comments: bool,
     }
 
-    impl <Iter: Iterator<Item = char>> Lexer<Iter> {
+    impl <Iter: Iterator<Item = char> + Clone> Lexer<Iter> {
 
         // Construct a new instance of Lexer with iter and 
         pub fn new(chars: Peekable<Iter>, source: Vec<String>) -> Self {
             let tokens = Vec::new();
             let current = ' ';
-            let line: usize = 1;
+            let diagnostics = Vec::new();
+
+            Self {
+                tokens, chars, current,
+                pos: 0, line: 1, col: 1,
+                next_pos: 0, next_line: 1, next_col: 1,
+                start: Span::default(),
+                source, diagnostics,
+                keep_comments: false,
+            }
+        }
 
-            Self { tokens, chars, current, line, source }
+        // Starts scanning from `start` rather than the beginning of the file,
+        // for lexing part of a file such as an interpolation or a REPL entry
+        pub fn starting_at(mut self, start: Span) -> Self {
+            self.next_pos = start.start;
+            self.next_line = start.line;
+            self.next_col = start.col;
+            self
+        }
+
+        // Keeps comments in the token stream as `Comment` tokens
+        // instead of skipping them
+        pub fn with_comments(mut self) -> Self {
+            self.keep_comments = true;
+            self
         }
 
         // Iterates through every character in the source file and appends tokens to self.tokens vec
-        // Does not return anything but does exhuast itself which sends the program back to main.rs
-        // where the tokens are handled further
-        pub fn scan(&mut self) {
+        // Errors are collected as diagnostics rather than stopping the scan, so every
+        // problem in the file is returned at once once the lexer exhausts itself
+        pub fn scan(&mut self) -> Result<(), Vec<Diagnostic>> {
             // Advance to first character
             if !self.advance() {
                 self.end();
-                // Return
+                return Ok(());
             }
 
             // Loop is constant until EOF reached
@@ -53,11 +92,14 @@ pub mod lexer {
                 }
 
                 // Attempt to match to symbol
+                self.mark();
                 let symbol = self.match_symbol();
                 match symbol {
                     // If symbol match is found
                     LexRes::Match(token) => {
-                        self.tokens.push(token);
+                        if token.token_type != TokenType::Comment || self.keep_comments {
+                            self.tokens.push(token);
+                        }
 
                         // Advance the iterator
                         if self.advance() {
@@ -70,31 +112,23 @@ pub mod lexer {
                     // If no symbol match found
                     LexRes::None => {
                         // Look for number literal
-                        if self.current.is_numeric() {
-                            let number = self.take_number();
-                            match number {
-                                // If we get a valid number literal back
-                                LexRes::Match(number) => {
-                                    let token = Token::new(TokenType::NumberLit, String::from(number), self.line);
-                                    self.tokens.push(token);
-
-                                    // Advance the iterator
-                                    if self.advance() {
-                                        continue 'scanner;
-                                    }
-                                    self.end();
-                                    break 'scanner;
-                                },
-
-                                // If we don't get a valid number literal
-                                LexRes::None => {},
-
-                                // If EOF reached
-                                LexRes::End => {
-                                    self.end();
-                                    break 'scanner;
-                                }
+                        if self.current.is_ascii_digit() {
+                            let number = match self.take_number() {
+                                LexRes::Match(number) => number,
+                                // Malformed literals have already been reported, a
+                                // placeholder keeps the parser from reporting a
+                                // missing operand as well
+                                _ => String::from("0"),
+                            };
+                            let token = Token::new(TokenType::NumberLit, number, self.span());
+                            self.tokens.push(token);
+
+                            // Advance the iterator
+                            if self.advance() {
+                                continue 'scanner;
                             }
+                            self.end();
+                            break 'scanner;
                         }
 
                         // If not a number literal, try identifier/keyword
@@ -114,8 +148,17 @@ pub mod lexer {
                                         self.end();
                                         break 'scanner;
                                     },
+                                    // If not a keyword, it must be an identifier
                                     LexRes::None => {
-                                        todo!("Match ID");
+                                        let token = Token::new(TokenType::Ident, result, self.span());
+                                        self.tokens.push(token);
+
+                                        // Advance the iterator
+                                        if self.advance() {
+                                            continue 'scanner;
+                                        }
+                                        self.end();
+                                        break 'scanner;
                                     },
                                     LexRes::End => {
                                         self.end();
@@ -126,7 +169,14 @@ pub mod lexer {
 
                             // If no valid identifier is found
                             LexRes::None => {
-                                todo!("Error handle no alphanum found");
+                                self.error(error::UNEXPECTED_CHAR, format!("Unexpected character `{}`", self.current));
+
+                                // Skip the character and keep scanning
+                                if self.advance() {
+                                    continue 'scanner;
+                                }
+                                self.end();
+                                break 'scanner;
                             },
 
                             // If EOF reached
@@ -144,114 +194,300 @@ pub mod lexer {
                     }
                 }
             }
+
+            if self.diagnostics.is_empty() {
+                Ok(())
+            } else {
+                Err(std::mem::take(&mut self.diagnostics))
+            }
         }
         
-        // Starts from current and takes every character until it reaches
-        // a non number. Employs exceptions for _ to improve readibility (e.g. 100_000)
-        // and . for floating point numbers
+        // Starts from current and takes every character that could be part of
+        // a number literal, then checks that it is well formed
+        // Literals are decimal integers like `100_000`, floats like `3.14` and
+        // `6.02e23`, or integers with a `0x`, `0b` or `0o` prefix
+        // The returned lexeme has the `_` separators removed, malformed literals
+        // are reported and return `LexRes::None`
+        // Peeks ahead so that current is left on the last char of the literal
         fn take_number(&mut self) -> LexRes<String> {
-            let mut buffer = String::new();
+            let mut raw = String::from(self.current);
+            let prefixed = self.current == '0' && matches!(self.chars.peek(), Some('x' | 'X' | 'b' | 'B' | 'o' | 'O'));
 
+            // Anything alphanumeric is taken, even if it can't be part of the
+            // literal, so `12abc` is reported as a whole rather than as `12`
+            // followed by `abc`
             'search: loop {
-                if self.current.is_numeric() {
-                    // Add number to buffer
-                    buffer.push(self.current);
+                match self.chars.peek().copied() {
+                    // A `.` followed by another `.` is a range like `1..10`
+                    Some('.') if self.peek_second() == Some('.') => break 'search,
+                    Some(c) if c.is_alphanumeric() || c == '_' || c == '.' => {},
+                    // The sign of an exponent, as in `1e-9`
+                    Some('+' | '-') if !prefixed && raw.ends_with(['e', 'E']) => {},
+                    _ => break 'search,
+                }
+                self.advance();
+                raw.push(self.current);
+            }
 
-                    // Advance the iterator
-                    if self.advance() {
-                        continue 'search;
-                    }
-                    return LexRes::End;
+            match validate_number(&raw) {
+                Ok(number) => LexRes::Match(number),
+                Err(message) => {
+                    self.error(error::MALFORMED_NUMBER, message);
+                    LexRes::None
+                },
+            }
+        }
+
+        // Starts from the opening `"` in current and takes the string up to
+        // and including the closing `"`, returning the final segment
+        // `"""` opens a string that may span multiple lines, with a newline
+        // straight after the opening quotes ignored. Raw strings, which start
+        // with `r`, keep `\` and `{` as they are
+        // Otherwise escapes are replaced with the characters they stand for and
+        // each `{<expr>}` ends the current segment, pushing it along with the
+        // tokens of the expression
+        fn take_string(&mut self, raw: bool) -> LexRes<Token> {
+            let triple = self.chars.peek() == Some(&'"') && self.peek_second() == Some('"');
+            if triple {
+                self.advance();
+                self.advance();
+                if self.chars.peek() == Some(&'\n') {
+                    self.advance();
                 }
+            }
 
-                // If not alphanumeric, match it to one of the exceptions
-                match self.current {
-                    '_' => {                   
-                        // Advance the iterator
-                        if self.advance() {
-                            continue 'search;
-                        }
+            let mut buffer = String::new();
+            let mut segment_start = self.start;
+
+            'search: loop {
+                match self.chars.peek() {
+                    None => {
+                        self.error(error::UNTERMINATED_STRING, String::from("Unterminated string literal"));
                         return LexRes::End;
                     },
-                    '.' => {
-                        // Add . to literal
-                        buffer.push('.');
-                        
-                        // Advance the iterator
-                        if self.advance() {
-                            continue 'search;
-                        }
-                        return LexRes::End;
+                    Some('\n') if !triple => {
+                        self.error(
+                            error::UNTERMINATED_STRING,
+                            String::from("Unterminated string literal, use `\"\"\"` for strings spanning multiple lines"),
+                        );
+                        break 'search;
                     },
-                    _ => return LexRes::Match(buffer),
+                    _ => {},
+                }
+                self.advance();
+
+                match self.current {
+                    '"' if !triple => break 'search,
+                    '"' if self.chars.peek() == Some(&'"') && self.peek_second() == Some('"') => {
+                        self.advance();
+                        self.advance();
+                        break 'search;
+                    },
+                    '\\' if !raw => self.take_escape(&mut buffer, triple),
+                    '{' if !raw => {
+                        // Segment before the `{`
+                        let span = Span { end: self.pos, end_line: self.line, end_col: self.col, ..segment_start };
+                        self.tokens.push(Token::new(TokenType::StringLit, std::mem::take(&mut buffer), span));
+
+                        self.take_interpolation(triple);
+                        segment_start = self.next_span();
+                    },
+                    c => buffer.push(c),
                 }
             }
+
+            LexRes::Match(Token::new(TokenType::StringLit, buffer, segment_start.to(self.here())))
         }
 
-        // Starts from current and advances until it reaches a ",
-        // signifying the termination of the string literal
-        // This method returns only the contents, not the " chars
-        fn take_string(&mut self) -> LexRes<String> {
-            let mut buffer = String::new();
+        // Starts from the `\` in current and pushes the character the escape
+        // stands for, leaving current on the last char of the escape
+        // Supports `\n`, `\t`, `\r`, `\0`, `\\`, `\"`, `\{`, `\}` and `\u{<hex>}`
+        fn take_escape(&mut self, buffer: &mut String, triple: bool) {
+            let start = self.here();
+            match self.chars.peek() {
+                // Left for `take_string` to report as unterminated
+                None => return,
+                Some('\n') if !triple => return,
+                _ => {},
+            }
+            self.advance();
+
+            let escaped = match self.current {
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                '0' => '\0',
+                '\\' | '"' | '{' | '}' => self.current,
+                'u' => {
+                    // `\u{...}` with 1 to 6 hex digits
+                    let mut digits = String::new();
+                    let mut closed = false;
+                    if self.chars.peek() == Some(&'{') {
+                        self.advance();
+                        while let Some(c) = self.chars.peek().copied() {
+                            if !c.is_ascii_hexdigit() || digits.len() == 6 {
+                                break;
+                            }
+                            self.advance();
+                            digits.push(c);
+                        }
+                        if self.chars.peek() == Some(&'}') {
+                            self.advance();
+                            closed = true;
+                        }
+                    }
 
+                    let code = u32::from_str_radix(&digits, 16).ok().filter(|_| closed);
+                    match code.and_then(char::from_u32) {
+                        Some(c) => c,
+                        None => {
+                            self.error_at(
+                                error::INVALID_ESCAPE,
+                                String::from("Invalid unicode escape, expected `\\u{<hex>}` with up to 6 hex digits"),
+                                start.to(self.here()),
+                            );
+                            return;
+                        },
+                    }
+                },
+                c => {
+                    self.error_at(
+                        error::INVALID_ESCAPE,
+                        format!("Unknown escape `\\{}`, use `\\\\` for a backslash", c),
+                        start.to(self.here()),
+                    );
+                    return;
+                },
+            };
+
+            buffer.push(escaped);
+        }
+
+        // Starts from the `{` of an interpolation in current and pushes the
+        // tokens of the expression inside between `InterpStart` and `InterpEnd`,
+        // leaving current on the closing `}`
+        // The expression is scanned by a lexer of it's own, which starts where
+        // the expression does so the tokens have the right spans
+        // If the `{` is never closed the expression stops before the next `"`,
+        // which is left to close the string
+        fn take_interpolation(&mut self, triple: bool) {
+            let open = self.here();
+            self.tokens.push(Token::new(TokenType::InterpStart, String::from("{"), open));
+
+            let closed = interpolation_closes(self.chars.clone(), triple);
+            let start = self.next_span();
+            let mut text = String::new();
+            let mut depth = 0;
+            let mut in_string = false;
             'search: loop {
-                // If character is the terminating " 
-                if self.current == '"' {
-                    // Determine if buffer is empty
-                    return LexRes::Match(buffer);
+                // Left for `take_string` to report as unterminated
+                match self.chars.peek() {
+                    None => break 'search,
+                    Some('\n') if !triple => break 'search,
+                    Some('"') if !closed && depth == 0 && !in_string => break 'search,
+                    _ => {},
+                }
+                self.advance();
+
+                match self.current {
+                    '}' if depth == 0 && !in_string => break 'search,
+                    '{' if !in_string => depth += 1,
+                    '}' if !in_string => depth -= 1,
+                    '"' => in_string = !in_string,
+                    // Skip over escaped quotes in strings inside the expression
+                    '\\' if in_string && self.chars.peek().is_some() => {
+                        text.push(self.current);
+                        self.advance();
+                    },
+                    _ => {},
                 }
+                text.push(self.current);
+            }
+
+            if !closed {
+                self.error_at(
+                    error::UNCLOSED_INTERPOLATION,
+                    String::from("Interpolation `{` is never closed, add a `}` after the expression"),
+                    open,
+                );
+            }
+
+            let mut lexer = Lexer::new(text.chars().peekable(), Vec::new()).starting_at(start);
+            lexer.keep_comments = self.keep_comments;
+            if let Err(diagnostics) = lexer.scan() {
+                self.diagnostics.extend(diagnostics);
+            }
+            self.tokens.extend(
+                lexer.tokens
+                    .into_iter()
+                    .filter(|t| !matches!(t.token_type, TokenType::NewLn | TokenType::EndFile)),
+            );
 
-                // Otherwise, add the character to the buffer
+            self.tokens.push(Token::new(TokenType::InterpEnd, String::from("}"), self.here()));
+        }
+
+        // Starts from the `|` in current and takes the rest of the comment
+        // Line comments stop before the newline so it is still tokenized,
+        // block comments end on the closing `*|`
+        // The lexeme includes the comment markers
+        fn take_comment(&mut self) -> LexRes<Token> {
+            let mut buffer = String::from("|");
+
+            // Line comment
+            if self.chars.peek() != Some(&'*') {
+                while let Some(c) = self.chars.peek() {
+                    if *c == '\n' {
+                        break;
+                    }
+                    self.advance();
+                    buffer.push(self.current);
+                }
+                return LexRes::Match(Token::new(TokenType::Comment, buffer, self.span()));
+            }
+
+            // Block comment
+            self.advance();
+            buffer.push('*');
+            'search: loop {
+                if !self.advance() {
+                    self.error(error::UNTERMINATED_COMMENT, String::from("Block comment is never closed, expected `*|`"));
+                    return LexRes::End;
+                }
                 buffer.push(self.current);
 
-                // Advance the iterator
-                if self.advance() {
-                    continue 'search;
+                if self.current == '*' && self.chars.peek() == Some(&'|') {
+                    self.advance();
+                    buffer.push('|');
+                    break 'search;
                 }
-                return LexRes::End;
             }
+
+            LexRes::Match(Token::new(TokenType::Comment, buffer, self.span()))
         }
 
         // Starts from current and takes every alphanumeric character until
         // it finds one that isn't alphanumeric, where it returns the chars it
         // has collected in `buffer`
+        // Peeks ahead so that current is left on the last char of the word
         fn take_alphanum(&mut self) -> LexRes<String> {
             let mut buffer = String::new();
+
+            // Make sure we are actually starting on a word
+            if !self.current.is_alphanumeric() && self.current != '_' {
+                return LexRes::None;
+            }
             
             'search: loop {
-                if self.current.is_alphanumeric() {
-                    // Add char to buffer if alphanum
-                    buffer.push(self.current);
-                    
-                    // Advance the iterator
-                    if self.advance() {
-                        continue 'search;
-                    }
-                    return LexRes::End;
-
-                } 
-                match self.current {
-                    // Make exception for underscores
-                    '_' => {
-                        // Add char to buffer if _
-                        buffer.push(self.current);
+                // Add char to buffer if alphanum or _
+                buffer.push(self.current);
 
-                        // Advance the iterator
-                        if self.advance() {
-                            continue 'search;
-                        }
-                        return LexRes::End;
-                    },
-                    
-                    // If character is not _ or alphanum, return buffer
-                    _ => {
-                        // If empty, return none, otherwise return buffer
-                        if !buffer.is_empty() {
-                            return LexRes::Match(buffer);
-                        } else {
-                            return LexRes::None;
-                        } 
+                // Only advance if the next char is still part of the word
+                match self.chars.peek() {
+                    Some(c) if c.is_alphanumeric() || *c == '_' => {
+                        self.advance();
+                        continue 'search;
                     },
+                    _ => return LexRes::Match(buffer),
                 }
             }
         }
@@ -260,7 +496,26 @@ pub mod lexer {
         // Returns a LexRes enum with the attached token if successful
         fn match_keyword(&self, string: &str) -> LexRes<Token> {
             match string {
-                "cout" => LexRes::Match(Token::new(TokenType::Cout, String::from("cout"), self.line)),
+                "cout" | "print" => LexRes::Match(Token::new(TokenType::Cout, String::from(string), self.span())),
+                "proc" => LexRes::Match(Token::new(TokenType::Proc, String::from("proc"), self.span())),
+                "returns" => LexRes::Match(Token::new(TokenType::Returns, String::from("returns"), self.span())),
+                "return" => LexRes::Match(Token::new(TokenType::Return, String::from("return"), self.span())),
+                "and" => LexRes::Match(Token::new(TokenType::And, String::from("and"), self.span())),
+                "or" => LexRes::Match(Token::new(TokenType::Or, String::from("or"), self.span())),
+                "not" => LexRes::Match(Token::new(TokenType::Not, String::from("not"), self.span())),
+                "while" => LexRes::Match(Token::new(TokenType::While, String::from("while"), self.span())),
+                "for" => LexRes::Match(Token::new(TokenType::For, String::from("for"), self.span())),
+                "in" => LexRes::Match(Token::new(TokenType::In, String::from("in"), self.span())),
+                "break" => LexRes::Match(Token::new(TokenType::Break, String::from("break"), self.span())),
+                "continue" => LexRes::Match(Token::new(TokenType::Continue, String::from("continue"), self.span())),
+                "if" => LexRes::Match(Token::new(TokenType::If, String::from("if"), self.span())),
+                "else" => LexRes::Match(Token::new(TokenType::Else, String::from("else"), self.span())),
+
+                // Boolean literals
+                "true" | "false" => LexRes::Match(Token::new(TokenType::BoolLit, String::from(string), self.span())),
+
+                // Type names
+                "integer" | "number" | "string" | "boolean" => LexRes::Match(Token::new(TokenType::Type, String::from(string), self.span())),
                 _ => LexRes::None,
             }
         }
@@ -270,65 +525,356 @@ pub mod lexer {
         // Returns a LexRes enum with the attached token if successful
         fn match_symbol(&mut self) -> LexRes<Token> {
             match self.current {
-                '=' => LexRes::Match(Token::new(TokenType::Equals, String::from("="), self.line)),
-                '!' => LexRes::Match(Token::new(TokenType::Bang, String::from("!"), self.line)),
-                '+' => LexRes::Match(Token::new(TokenType::Plus, String::from("+"), self.line)),
-                '-' => LexRes::Match(Token::new(TokenType::Minus, String::from("-"), self.line)),
-                '/' => LexRes::Match(Token::new(TokenType::Slash, String::from("/"), self.line)),
-                '*' => LexRes::Match(Token::new(TokenType::Star, String::from("*"), self.line)),
-                '%' => LexRes::Match(Token::new(TokenType::Percent, String::from("%"), self.line)),
-
-                // String literal
-                '"' => {
-                    if self.advance() {
-                        let string = self.take_string();
-                        match string {
-                            // If a string literal is found
-                            LexRes::Match(literal) => return LexRes::Match(Token::new(TokenType::StringLit, literal, self.line)),
-                            
-                            // If the string literal is empty
-                            LexRes::None => {
-                                todo!("Error handling  string or something ig");
-                            },
-
-                            // If EOF reached
-                            LexRes::End => return LexRes::End,
-                        }
+                '=' => self.match_double('=', TokenType::EqualEqual, TokenType::Equals),
+                '!' => self.match_double('=', TokenType::BangEqual, TokenType::Bang),
+                '<' => self.match_double('=', TokenType::LessEqual, TokenType::Less),
+                '>' => self.match_double('=', TokenType::GreaterEqual, TokenType::Greater),
+                '+' => LexRes::Match(Token::new(TokenType::Plus, String::from("+"), self.span())),
+                '-' => {
+                    // `->` reassignment or a single minus
+                    if self.chars.peek() == Some(&'>') {
+                        self.advance();
+                        return LexRes::Match(Token::new(TokenType::Arrow, String::from("->"), self.span()));
                     }
-
-                    eprintln!("Error handle here, non-terminating literal");
-                    LexRes::End
-                }
+                    LexRes::Match(Token::new(TokenType::Minus, String::from("-"), self.span()))
+                },
+                '/' => LexRes::Match(Token::new(TokenType::Slash, String::from("/"), self.span())),
+                '*' => LexRes::Match(Token::new(TokenType::Star, String::from("*"), self.span())),
+                '%' => LexRes::Match(Token::new(TokenType::Percent, String::from("%"), self.span())),
+                '^' => LexRes::Match(Token::new(TokenType::Caret, String::from("^"), self.span())),
+                '(' => LexRes::Match(Token::new(TokenType::LParen, String::from("("), self.span())),
+                ')' => LexRes::Match(Token::new(TokenType::RParen, String::from(")"), self.span())),
+                ',' => LexRes::Match(Token::new(TokenType::Comma, String::from(","), self.span())),
+                '.' => {
+                    // Only `..` is a symbol, a lone `.` is unexpected
+                    if self.chars.peek() == Some(&'.') {
+                        self.advance();
+                        return LexRes::Match(Token::new(TokenType::DotDot, String::from(".."), self.span()));
+                    }
+                    LexRes::None
+                },
+                '{' => LexRes::Match(Token::new(TokenType::LBrace, String::from("{"), self.span())),
+                '}' => LexRes::Match(Token::new(TokenType::RBrace, String::from("}"), self.span())),
+                ':' => LexRes::Match(Token::new(TokenType::Colon, String::from(":"), self.span())),
+
+                // String literals, `r"..."` being a raw string
+                '"' => self.take_string(false),
+                'r' if self.chars.peek() == Some(&'"') => {
+                    self.advance();
+                    self.take_string(true)
+                },
+
+                // Line `| ...` and block `|* ... *|` comments
+                '|' => self.take_comment(),
 
                 // Newline
-                '\n' => {
-                    self.line += 1;
-                    LexRes::Match(Token::new(TokenType::NewLn, String::from("newline"), self.line - 1))
-                }
+                '\n' => LexRes::Match(Token::new(TokenType::NewLn, String::from("newline"), self.span())),
 
                 // No matches found
                 _ => LexRes::None,
             }
         }
 
+        // Records an error diagnostic spanning the token being scanned
+        fn error(&mut self, code: &'static str, message: String) {
+            self.error_at(code, message, self.span());
+        }
+
+        fn error_at(&mut self, code: &'static str, message: String, span: Span) {
+            self.diagnostics.push(Diagnostic::error(code, message, span));
+        }
+
+        // Marks current as the first character of a new token
+        fn mark(&mut self) {
+            self.start = Span {
+                start: self.pos,
+                end: self.pos,
+                line: self.line,
+                col: self.col,
+                end_line: self.line,
+                end_col: self.col,
+            };
+        }
+
+        // Span of current alone
+        fn here(&self) -> Span {
+            Span {
+                start: self.pos,
+                end: self.pos + self.current.len_utf8(),
+                line: self.line,
+                col: self.col,
+                end_line: self.line,
+                end_col: self.col + 1,
+            }
+        }
+
+        // Empty span where the next char will be
+        fn next_span(&self) -> Span {
+            Span {
+                start: self.next_pos,
+                end: self.next_pos,
+                line: self.next_line,
+                col: self.next_col,
+                end_line: self.next_line,
+                end_col: self.next_col,
+            }
+        }
+
+        // Span from the last mark up to and including current
+        fn span(&self) -> Span {
+            Span {
+                end: self.pos + self.current.len_utf8(),
+                end_line: self.line,
+                end_col: self.col + 1,
+                ..self.start
+            }
+        }
+
+        // Matches a two char symbol like `==` if the next char is `second`,
+        // otherwise matches the single char symbol in current
+        fn match_double(&mut self, second: char, double: TokenType, single: TokenType) -> LexRes<Token> {
+            let mut lexeme = String::from(self.current);
+            if self.chars.peek() == Some(&second) {
+                self.advance();
+                lexeme.push(second);
+                return LexRes::Match(Token::new(double, lexeme, self.span()));
+            }
+            LexRes::Match(Token::new(single, lexeme, self.span()))
+        }
+
         // Pushes a new EOF token to the end of the tokens vec in Lexer
         // Simply used as a way to simplify called the termination of the parser
         // rather than typing this all out every single time a helper
         // method returns `LexRes::End``
         fn end(&mut self) {
-            let end_token = Token::end(self.line);
+            let end_token = Token::end(self.next_span());
             self.tokens.push(end_token);
         }
         
+        // Looks two chars ahead of current without advancing
+        fn peek_second(&self) -> Option<char> {
+            let mut chars = self.chars.clone();
+            chars.next();
+            chars.next()
+        }
+
         // Helper function returns true if can advance
-        // Sets self.current to updated character
+        // Sets self.current to updated character and tracks it's
+        // byte offset, line and column
         // Returns false if EOF
         fn advance(&mut self) -> bool {
             if let Some(c) = self.chars.next() {
                 self.current = c;
+                self.pos = self.next_pos;
+                self.line = self.next_line;
+                self.col = self.next_col;
+
+                // Work out where the next char will be
+                self.next_pos += c.len_utf8();
+                if c == '\n' {
+                    self.next_line += 1;
+                    self.next_col = 1;
+                } else {
+                    self.next_col += 1;
+                }
                 return true;
             }
             false
         }
     }
-}
\ No newline at end of file
+
+    // Whether an interpolation has a closing `}`, given the characters after
+    // it's `{`, following the same rules as `Lexer::take_interpolation`
+    fn interpolation_closes(mut chars: impl Iterator<Item = char>, triple: bool) -> bool {
+        let mut depth = 0;
+        let mut in_string = false;
+        while let Some(c) = chars.next() {
+            match c {
+                '\n' if !triple => return false,
+                '}' if depth == 0 && !in_string => return true,
+                '{' if !in_string => depth += 1,
+                '}' if !in_string => depth -= 1,
+                '"' => in_string = !in_string,
+                '\\' if in_string => {
+                    chars.next();
+                },
+                _ => {},
+            }
+        }
+        false
+    }
+
+    // Checks a number literal taken by `Lexer::take_number`, returning it
+    // with the `_` separators removed or a message explaining what is wrong
+    fn validate_number(raw: &str) -> Result<String, String> {
+        // Prefixed integers
+        let radix = match raw.get(..2) {
+            Some("0x" | "0X") => Some((16, "hexadecimal")),
+            Some("0b" | "0B") => Some((2, "binary")),
+            Some("0o" | "0O") => Some((8, "octal")),
+            _ => None,
+        };
+        if let Some((radix, name)) = radix {
+            let digits = &raw[2..];
+            if digits.is_empty() {
+                return Err(format!("Expected {} digits after `{}`", name, raw));
+            }
+            if let Some(c) = digits.chars().find(|c| *c != '_' && !c.is_digit(radix)) {
+                return Err(format!("Invalid digit `{}` in {} literal `{}`", c, name, raw));
+            }
+            check_separators(digits, raw)?;
+            return Ok(format!("0{}{}", raw[1..2].to_lowercase(), digits.replace('_', "")));
+        }
+
+        // Decimal integers and floats, split into `<whole>.<fraction>e<exponent>`
+        let (mantissa, exponent) = match raw.find(['e', 'E']) {
+            Some(i) => (&raw[..i], Some(&raw[i + 1..])),
+            None => (raw, None),
+        };
+        let (whole, fraction) = match mantissa.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (mantissa, None),
+        };
+
+        if let Some(c) = raw.chars().find(|c| !c.is_ascii_digit() && !matches!(c, '_' | '.' | 'e' | 'E' | '+' | '-')) {
+            return Err(format!("Invalid character `{}` in number literal `{}`", c, raw));
+        }
+        if fraction.is_some_and(|fraction| fraction.contains('.')) {
+            return Err(format!("Number literal `{}` has more than one `.`", raw));
+        }
+        check_separators(whole, raw)?;
+
+        if let Some(fraction) = fraction {
+            if fraction.is_empty() {
+                return Err(format!("Expected digits after the `.` in `{}`", raw));
+            }
+            check_separators(fraction, raw)?;
+        }
+
+        if let Some(exponent) = exponent {
+            let digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+            if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit() || c == '_') {
+                return Err(format!("Expected digits in the exponent of `{}`", raw));
+            }
+            check_separators(digits, raw)?;
+        }
+
+        Ok(raw.replace('_', ""))
+    }
+
+    // `_` separators are only allowed between two digits
+    fn check_separators(digits: &str, raw: &str) -> Result<(), String> {
+        if digits.starts_with('_') || digits.ends_with('_') || digits.contains("__") {
+            return Err(format!("`_` can only appear between digits, as in `1_000`, but found `{}`", raw));
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use crate::error::error;
+        use crate::token::token::{Token, TokenType};
+        use super::{validate_number, Lexer};
+
+        // Lexes `source`, returning it's tokens or the code of each error
+        fn lex(source: &str) -> Result<Vec<Token>, Vec<&'static str>> {
+            let mut lexer = Lexer::new(source.chars().peekable(), Vec::new());
+            match lexer.scan() {
+                Ok(()) => Ok(lexer.tokens),
+                Err(diagnostics) => Err(diagnostics.iter().map(|d| d.code).collect()),
+            }
+        }
+
+        // The contents of `source`, which should be a single string literal
+        fn string(source: &str) -> String {
+            let tokens = lex(source).expect("source should lex");
+            match &tokens[..] {
+                [token, end] if token.token_type == TokenType::StringLit && end.token_type == TokenType::EndFile => token.lexeme.clone(),
+                tokens => panic!("expected a single string literal, found {:?}", tokens),
+            }
+        }
+
+        #[test]
+        fn accepts_and_normalizes_numbers() {
+            assert_eq!(validate_number("42"), Ok(String::from("42")));
+            assert_eq!(validate_number("1_000_000"), Ok(String::from("1000000")));
+            assert_eq!(validate_number("3.14"), Ok(String::from("3.14")));
+            assert_eq!(validate_number("1.5e-3"), Ok(String::from("1.5e-3")));
+            assert_eq!(validate_number("2E+10"), Ok(String::from("2E+10")));
+            assert_eq!(validate_number("0XfF_ff"), Ok(String::from("0xfFff")));
+            assert_eq!(validate_number("0b1010"), Ok(String::from("0b1010")));
+            assert_eq!(validate_number("0o17"), Ok(String::from("0o17")));
+        }
+
+        #[test]
+        fn rejects_malformed_numbers() {
+            for raw in [
+                "0x", "0xfg", "0b102", "0o8",
+                "1..2", "1.2.3", "1.", "1e", "1e+", "2ex",
+                "_1", "1_", "1__0", "1._5", "0x_ff",
+            ] {
+                assert!(validate_number(raw).is_err(), "`{}` should be rejected", raw);
+            }
+        }
+
+        #[test]
+        fn escapes() {
+            assert_eq!(string(r#""\n\t\r\0\\\"\{\}""#), "\n\t\r\0\\\"{}");
+            assert_eq!(lex(r#""\q""#).unwrap_err(), [error::INVALID_ESCAPE]);
+        }
+
+        #[test]
+        fn unicode_escapes() {
+            assert_eq!(string(r#""\u{41}""#), "A");
+            assert_eq!(string(r#""\u{1F600}""#), "\u{1F600}");
+            assert_eq!(string(r#""\u{10FFFF}""#), "\u{10FFFF}");
+            for source in [r#""\u41""#, r#""\u{}""#, r#""\u{1234567}""#, r#""\u{110000}""#, r#""\u{D800}""#] {
+                assert_eq!(lex(source).unwrap_err(), [error::INVALID_ESCAPE], "`{}` should be rejected", source);
+            }
+        }
+
+        #[test]
+        fn raw_strings_keep_backslashes_and_braces() {
+            assert_eq!(string(r#"r"C:\new\{x}""#), r"C:\new\{x}");
+        }
+
+        #[test]
+        fn multi_line_strings_skip_the_first_newline() {
+            assert_eq!(string("\"\"\"\nline one\n  line \"two\"\n\"\"\""), "line one\n  line \"two\"\n");
+            assert_eq!(string("\"\"\"one line\"\"\""), "one line");
+        }
+
+        #[test]
+        fn interpolation_can_hold_strings() {
+            let tokens = lex(r#""a{"b"}c""#).unwrap();
+            let lexemes: Vec<(TokenType, &str)> = tokens.iter().map(|t| (t.token_type.clone(), t.lexeme.as_str())).collect();
+            assert_eq!(lexemes, [
+                (TokenType::StringLit, "a"),
+                (TokenType::InterpStart, "{"),
+                (TokenType::StringLit, "b"),
+                (TokenType::InterpEnd, "}"),
+                (TokenType::StringLit, "c"),
+                (TokenType::EndFile, "<-- END OF FILE -->"),
+            ]);
+        }
+
+        #[test]
+        fn unclosed_interpolation_is_one_error() {
+            assert_eq!(lex("\"{x\"").unwrap_err(), [error::UNCLOSED_INTERPOLATION]);
+            assert_eq!(lex("\"a {f(\"b\") c\"\nx = 1").unwrap_err().first(), Some(&error::UNCLOSED_INTERPOLATION));
+        }
+
+        #[test]
+        fn interpolated_tokens_have_source_spans() {
+            let tokens = lex("x = \"ab{y + 1}\"\n\"\"\"\n{z}\"\"\"").unwrap();
+            let find = |lexeme: &str| tokens.iter().find(|t| t.lexeme == lexeme).expect("token should be lexed").span;
+
+            let y = find("y");
+            assert_eq!((y.start, y.line, y.col, y.end_col), (8, 1, 9, 10));
+            let one = find("1");
+            assert_eq!((one.start, one.line, one.col), (12, 1, 13));
+            let z = find("z");
+            assert_eq!((z.line, z.col), (3, 2));
+        }
+    }
+}
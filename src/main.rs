@@ -1,53 +1,171 @@
-use std::{env, fs::File, io::Read};
+#![allow(clippy::module_inception)]
+
+use std::{env, fs, io::{self, IsTerminal, Read}, process, thread};
 use lexer::lexer::Lexer;
 use token::token::{Token, TokenType};
 
 use crate::ast::ast::Ast;
+use crate::check::check::Checker;
+use crate::cli::cli::{parse_args, Command, USAGE};
+use crate::error::error::{Diagnostic, Renderer};
+use crate::eval::eval::Evaluator;
+use crate::printer::printer::{dot, tree};
+use crate::repl::repl::Repl;
 
 mod token;
 mod lexer;
 mod ast;
+mod check;
+mod eval;
+mod error;
+mod printer;
+mod repl;
+mod cli;
+
 
+// The evaluator recurses through the tree for every procedure call, so the
+// interpreter runs on a thread with a larger stack than the main thread's
+// to leave room for `MAX_CALL_DEPTH` nested calls, even in debug builds
+const STACK_SIZE: usize = 64 * 1024 * 1024;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let interpreter = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(start)
+        .expect("Could not start the interpreter thread");
+
+    // A panic has already been printed by the thread
+    if interpreter.join().is_err() {
+        process::exit(101);
+    }
+}
+
+fn start() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {}\nRun with --help to see the usage", message);
+            process::exit(2);
+        }
+    };
+    if options.help {
+        println!("{}", USAGE);
+        return;
+    }
+
+    // Colour is only used when writing to a terminal and NO_COLOR is unset
+    let color = !options.no_color && io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none();
+
+    // Get the file name and source, from stdin if asked for or piped in
+    let (file, read) = match options.path.as_deref() {
+        Some("-") => ("<stdin>", read_stdin()),
+        Some(path) => (path, fs::read_to_string(path)),
+        None if !io::stdin().is_terminal() => ("<stdin>", read_stdin()),
+        None if options.command == Command::Run => {
+            // No file given, so start the REPL
+            if let Err(e) = Repl::new(color).run() {
+                eprintln!("REPL stopped: {}", e);
+                process::exit(1);
+            }
+            return;
+        },
+        None => {
+            eprintln!("error: `{}` needs a file, or `-` to read from stdin", options.command.name());
+            process::exit(2);
+        },
+    };
+    let file_buffer = match read {
+        Ok(buffer) => buffer,
+        Err(e) => {
+            eprintln!("error: Could not read {}: {}", file, e);
+            process::exit(1);
+        }
+    };
 
-    // Get file and flags
-    if args.len() >= 2 {        
-        // Get file and make buffer
-        let mut file = File::open(&args[1]).expect("Error reading file!");
-        let mut file_buffer = String::new();
-        file.read_to_string(&mut file_buffer).expect("Error");
+    let lines: Vec<String> = file_buffer.split("\n").map(|x| x.to_string()).collect();
+    let chars = file_buffer.chars().peekable();
+
+    // Comments are only of interest when printing the tokens
+    let mut lexer = Lexer::new(chars, lines);
+    if options.command == Command::Tokens {
+        lexer = lexer.with_comments();
+    }
+    let mut diagnostics = Vec::<Diagnostic>::new();
+    if let Err(errors) = lexer.scan() {
+        diagnostics.extend(errors);
+    }
 
-        let lines: Vec<String> = file_buffer.split("\n").map(|x| x.to_string()).collect();
-        let chars = file_buffer.chars().peekable();
-        
-        let mut lexer = Lexer::new(chars, lines);
-        lexer.scan();
+    let renderer = Renderer::new(file, &lexer.source, color);
+    if options.command == Command::Tokens {
+        for token in &lexer.tokens {
+            println!("{}", token);
+        }
+        report(&renderer, diagnostics);
+        return;
+    }
 
-        println!("{:#?}", &lexer.tokens);
+    // Parse even if the lexer failed so parser errors are reported too
+    let mut parser = match Ast::new(lexer.tokens) {
+        Ok(parser) => parser,
+        Err(e) => {
+            eprint!("{}", renderer.render(&e));
+            process::exit(1);
+        }
+    };
+    if let Err(errors) = parser.parse() {
+        diagnostics.extend(errors);
+    }
 
-        let mut parser = Ast::new(lexer.tokens);
-        parser.parse();
+    // The tree is printed even if there were errors, since the program node
+    // still holds every statement that parsed
+    if options.command == Command::Ast || options.command == Command::Dot {
+        if options.command == Command::Ast {
+            print!("{}", tree(&parser, parser.root));
+        } else {
+            print!("{}", dot(&parser, parser.root));
+        }
+        report(&renderer, diagnostics);
+        return;
+    }
 
-        for node in parser.tree {
-            println!("{:#?}", node);
+    // Only check types once the tree is complete, otherwise missing
+    // pieces would be reported as mismatches
+    if diagnostics.is_empty() {
+        if let Err(errors) = Checker::new(&parser).check() {
+            diagnostics.extend(errors);
         }
-        
-        // Print each token and it's corresponding line content
-        // for token in lexer.tokens {
-        //     println!("{:?}", token);
+    }
 
-        //     // Find the line content
-        //     let line_content = &lexer.source[token.line - 1];
-        //     println!("{line_content}");
-        // }
+    // Report every error found before evaluating anything
+    report(&renderer, diagnostics);
+    if options.command == Command::Check {
+        println!("{}: no errors", file);
+        return;
+    }
+
+    // Evaluate the tree and print the result of each expression
+    let mut stdout = io::stdout();
+    let mut evaluator = Evaluator::new(&parser, &mut stdout);
+    if let Err(e) = evaluator.run() {
+        report(&renderer, vec![e]);
+    }
+}
 
-        // println!("{lines:#?}");
-        // println!("{}", lines.len());
+fn read_stdin() -> io::Result<String> {
+    let mut buffer = String::new();
+    io::stdin().read_to_string(&mut buffer)?;
+    Ok(buffer)
+}
+
+// Prints any diagnostics in source order and exits if there were some
+fn report(renderer: &Renderer, mut diagnostics: Vec<Diagnostic>) {
+    if diagnostics.is_empty() {
+        return;
+    }
 
-    } else {
-        eprintln!("File path not specified.");
-        std::process::exit(1);
+    diagnostics.sort_by_key(|d| (d.span.line, d.span.col));
+    for diagnostic in &diagnostics {
+        eprintln!("{}", renderer.render(diagnostic));
     }
+    process::exit(1);
 }
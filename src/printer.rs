@@ -0,0 +1,124 @@
+pub mod printer {
+    use crate::ast::{ast::{Ast, NodeKey}, node::{LiteralType, Node, UnaryOp}};
+
+    // Prints the tree beneath `key` on one line as an s-expression,
+    // e.g. `1 + 2 * 3` is `(+ 1 (* 2 3))`
+    // Parentheses in the source are left out since the nesting already shows them
+    pub fn sexpr(ast: &Ast, key: NodeKey) -> String {
+        let node = &ast.tree[key];
+        if let Node::Grouping(inner) = node {
+            return sexpr(ast, *inner);
+        }
+
+        let children = node.children();
+        if children.is_empty() {
+            return label(node);
+        }
+
+        let children: Vec<String> = children.into_iter().map(|child| sexpr(ast, child)).collect();
+        format!("({} {})", label(node), children.join(" "))
+    }
+
+    // Prints the tree beneath `key` with one node per line, each indented
+    // under it's parent, e.g. `x = 1 + 2` is
+    // = x
+    // └── +
+    //     ├── 1
+    //     └── 2
+    // Unlike `sexpr` groupings are kept, so the tree is exactly what the parser built
+    pub fn tree(ast: &Ast, key: NodeKey) -> String {
+        let mut out = label(&ast.tree[key]);
+        out.push('\n');
+        tree_children(ast, key, "", &mut out);
+        out
+    }
+
+    // Writes the children of `key`, each line starting with `prefix` to
+    // carry on the branches of the nodes above
+    fn tree_children(ast: &Ast, key: NodeKey, prefix: &str, out: &mut String) {
+        let children = ast.tree[key].children();
+        for (i, child) in children.iter().enumerate() {
+            let last = i + 1 == children.len();
+            let (branch, next) = if last { ("└── ", "    ") } else { ("├── ", "│   ") };
+
+            out.push_str(prefix);
+            out.push_str(branch);
+            out.push_str(&label(&ast.tree[*child]));
+            out.push('\n');
+            tree_children(ast, *child, &format!("{}{}", prefix, next), out);
+        }
+    }
+
+    // Writes the tree beneath `key` as a Graphviz graph, which can be drawn
+    // with `dot -Tsvg`. Children are drawn left to right in source order
+    pub fn dot(ast: &Ast, key: NodeKey) -> String {
+        let mut out = String::from("digraph ast {\n    ordering=out;\n    node [shape=box, fontname=monospace];\n");
+        dot_node(ast, key, &mut 0, &mut out);
+        out.push_str("}\n");
+        out
+    }
+
+    // Writes a node and the edges to it's children, returning the node's id
+    fn dot_node(ast: &Ast, key: NodeKey, next_id: &mut usize, out: &mut String) -> usize {
+        let id = *next_id;
+        *next_id += 1;
+
+        let label = label(&ast.tree[key]).replace('\\', "\\\\").replace('"', "\\\"");
+        out.push_str(&format!("    n{} [label=\"{}\"];\n", id, label));
+        for child in ast.tree[key].children() {
+            let child_id = dot_node(ast, child, next_id, out);
+            out.push_str(&format!("    n{} -> n{};\n", id, child_id));
+        }
+        id
+    }
+
+    // Short description of a node, without it's children
+    fn label(node: &Node) -> String {
+        match node {
+            Node::BinaryExpr(expr) => expr.op.to_string(),
+            Node::UnaryExpr(expr) => match expr.op {
+                UnaryOp::Negate => String::from("-"),
+                UnaryOp::Not => String::from("not"),
+            },
+            Node::Grouping(_) => String::from("group"),
+
+            Node::StringLitExpr(lit) | Node::NumberLitExpr(lit) | Node::IntegerLitExpr(lit) | Node::BooleanLitExpr(lit) => {
+                match &lit.typ {
+                    LiteralType::String(s) => format!("{:?}", s),
+                    LiteralType::Number(n) => format!("{:?}", n),
+                    LiteralType::Integer(n) => n.to_string(),
+                    LiteralType::BigInteger(n) => n.to_string(),
+                    LiteralType::Boolean(b) => b.to_string(),
+                }
+            },
+            Node::Concat(_) => String::from("concat"),
+            Node::Cast(cast) => cast.typ.to_string(),
+
+            Node::Ident(name) => name.clone(),
+            Node::VarDecl(decl) => match decl.typ {
+                Some(typ) => format!("= {}: {}", decl.name, typ),
+                None => format!("= {}", decl.name),
+            },
+            Node::Assign(assign) => format!("-> {}", assign.name),
+
+            Node::ProcDecl(decl) => {
+                let params: Vec<String> = decl.params.iter().map(|param| format!("{}: {}", param.name, param.typ)).collect();
+                match decl.returns {
+                    Some(typ) => format!("proc {}({}) returns {}", decl.name, params.join(", "), typ),
+                    None => format!("proc {}({})", decl.name, params.join(", ")),
+                }
+            },
+            Node::Call(_) => String::from("call"),
+            Node::Return(_) => String::from("return"),
+
+            Node::Print(_) => String::from("print"),
+            Node::If(_) => String::from("if"),
+            Node::While(_) => String::from("while"),
+            Node::For(stmt) => format!("for {}", stmt.var),
+            Node::Break => String::from("break"),
+            Node::Continue => String::from("continue"),
+            Node::Block(_) => String::from("block"),
+            Node::Program(_) => String::from("program"),
+        }
+    }
+}
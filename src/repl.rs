@@ -0,0 +1,268 @@
+pub mod repl {
+    use rustyline::{error::ReadlineError, DefaultEditor};
+    use crate::ast::ast::{Ast, NodeKey};
+    use crate::check::check::{Checker, Scopes};
+    use crate::error::error::{Diagnostic, Renderer, Span};
+    use crate::eval::{env::Environment, eval::Evaluator};
+    use crate::lexer::lexer::Lexer;
+    use crate::printer::printer;
+    use crate::token::token::{Token, TokenType};
+
+    const HELP: &str = "\
+Type a statement or expression and press enter to run it
+Blocks and brackets left open carry on to the next line, enter a blank line to stop early
+Use the up and down arrow keys to go back through earlier entries
+
+Commands:
+  :help          Show this message
+  :type <expr>   Show the type of an expression without running it
+  :ast <expr>    Show how an expression is parsed
+  :quit          Leave the REPL, as does Ctrl-D";
+
+    // Reads entries a line at a time and runs each one as soon as it is
+    // complete, keeping variables and procedures between entries
+    // Every entry is parsed into the same tree, so procedures declared in
+    // one entry can be called from the next
+    pub struct Repl {
+        ast: Ast,
+        env: Environment,
+        scopes: Scopes,
+
+        // Every line entered so far and the total length in bytes, so each
+        // entry is lexed where it would be if the session was one file and
+        // diagnostics from earlier entries still point at the right line
+        source: Vec<String>,
+        offset: usize,
+
+        color: bool,
+    }
+
+    impl Repl {
+        pub fn new(color: bool) -> Self {
+            let ast = Ast::new(vec![Token::end(Span::line(1))]).expect("Token stream holds an EOF token");
+            Self { ast, env: Environment::new(), scopes: Scopes::default(), source: Vec::new(), offset: 0, color }
+        }
+
+        // Runs until the user quits or input ends
+        pub fn run(&mut self) -> Result<(), ReadlineError> {
+            let mut editor = DefaultEditor::new()?;
+            println!("Darcy {}, type :help for help", env!("CARGO_PKG_VERSION"));
+
+            let mut entry = String::new();
+            loop {
+                let prompt = if entry.is_empty() { ">> " } else { ".. " };
+                let line = match editor.readline(prompt) {
+                    Ok(line) => line,
+                    // Ctrl-C drops the unfinished entry
+                    Err(ReadlineError::Interrupted) => {
+                        entry.clear();
+                        continue;
+                    },
+                    Err(ReadlineError::Eof) => return Ok(()),
+                    Err(e) => return Err(e),
+                };
+
+                if entry.is_empty() && line.trim_start().starts_with(':') {
+                    editor.add_history_entry(line.trim())?;
+                    if !self.command(line.trim()) {
+                        return Ok(());
+                    }
+                    continue;
+                }
+
+                entry.push_str(&line);
+                entry.push('\n');
+                if entry.trim().is_empty() {
+                    entry.clear();
+                    continue;
+                }
+
+                // Keep reading while something is left open, unless the line is blank
+                if !line.trim().is_empty() && is_incomplete(&entry) {
+                    continue;
+                }
+
+                editor.add_history_entry(entry.trim_end())?;
+                let entry = std::mem::take(&mut entry);
+                self.run_entry(&entry);
+            }
+        }
+
+        // Runs a `:` command, returning false if the REPL should stop
+        fn command(&mut self, line: &str) -> bool {
+            let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            match name {
+                ":help" | ":h" => println!("{}", HELP),
+                ":quit" | ":q" => return false,
+                ":type" | ":t" => {
+                    if let Some(key) = self.parse_expr(rest) {
+                        let mut checker = Checker::resume(&self.ast, self.scopes.clone());
+                        match checker.type_of(key) {
+                            Ok(typ) => println!("{}", typ),
+                            Err(diagnostics) => self.report(diagnostics),
+                        }
+                    }
+                },
+                ":ast" => {
+                    if let Some(key) = self.parse_expr(rest) {
+                        println!("{}", printer::sexpr(&self.ast, key));
+                    }
+                },
+                _ => println!("Unknown command `{}`, type :help for a list of commands", name),
+            }
+            true
+        }
+
+        // Lexes, parses, checks and evaluates an entry, reporting any errors
+        fn run_entry(&mut self, entry: &str) {
+            let body = match self.parse(entry) {
+                Some(body) => body,
+                None => return,
+            };
+
+            // Only keep what the checker learned if the entry is going to run
+            let mut checker = Checker::resume(&self.ast, self.scopes.clone());
+            if let Err(diagnostics) = checker.check_statements(&body) {
+                self.report(diagnostics);
+                return;
+            }
+            self.scopes = checker.into_scopes();
+
+            let mut stdout = std::io::stdout();
+            let env = std::mem::take(&mut self.env);
+            let mut evaluator = Evaluator::resume(&self.ast, env, &mut stdout);
+            let result = evaluator.run_statements(&body);
+            self.env = evaluator.into_env();
+
+            if let Err(diagnostic) = result {
+                self.report(vec![diagnostic]);
+            }
+        }
+
+        // Parses the expression given to a command like `:type`
+        fn parse_expr(&mut self, source: &str) -> Option<NodeKey> {
+            if source.trim().is_empty() {
+                println!("Expected an expression after the command, e.g. `:type 1 + 2`");
+                return None;
+            }
+
+            match self.parse(&format!("{}\n", source))?[..] {
+                [key] => Some(key),
+                _ => {
+                    println!("Expected a single expression");
+                    None
+                },
+            }
+        }
+
+        // Lexes and parses an entry into the tree, returning the keys of it's
+        // statements or reporting every error found in it
+        fn parse(&mut self, entry: &str) -> Option<Vec<NodeKey>> {
+            let start = Span {
+                start: self.offset,
+                end: self.offset,
+                line: self.source.len() + 1,
+                col: 1,
+                end_line: self.source.len() + 1,
+                end_col: 1,
+            };
+            self.source.extend(entry.lines().map(String::from));
+            self.offset += entry.len();
+
+            let mut lexer = Lexer::new(entry.chars().peekable(), Vec::new()).starting_at(start);
+            let mut diagnostics = Vec::<Diagnostic>::new();
+            if let Err(errors) = lexer.scan() {
+                diagnostics.extend(errors);
+            }
+
+            let body = match self.ast.parse_more(lexer.tokens) {
+                Ok(body) => body,
+                Err(errors) => {
+                    diagnostics.extend(errors);
+                    Vec::new()
+                },
+            };
+
+            if diagnostics.is_empty() {
+                Some(body)
+            } else {
+                self.report(diagnostics);
+                None
+            }
+        }
+
+        fn report(&self, mut diagnostics: Vec<Diagnostic>) {
+            let renderer = Renderer::new("<repl>", &self.source, self.color);
+            diagnostics.sort_by_key(|d| (d.span.line, d.span.col));
+            for diagnostic in &diagnostics {
+                eprintln!("{}", renderer.render(diagnostic));
+            }
+        }
+    }
+
+    // Whether an entry has something left open, meaning it carries on to the
+    // next line: an unclosed `{`, `(` or `"""`, or a trailing binary operator,
+    // which is the only place outside brackets the parser allows a newline
+    fn is_incomplete(entry: &str) -> bool {
+        if entry.matches("\"\"\"").count() % 2 == 1 {
+            return true;
+        }
+
+        let mut lexer = Lexer::new(entry.chars().peekable(), Vec::new());
+        let _ = lexer.scan();
+
+        let mut depth: isize = 0;
+        for token in &lexer.tokens {
+            match token.token_type {
+                TokenType::LBrace | TokenType::LParen => depth += 1,
+                TokenType::RBrace | TokenType::RParen => depth -= 1,
+                _ => {},
+            }
+        }
+
+        let last = lexer.tokens
+            .iter()
+            .rev()
+            .find(|t| !matches!(t.token_type, TokenType::NewLn | TokenType::EndFile));
+        let trailing = last.is_some_and(|t| matches!(
+            t.token_type,
+            TokenType::Plus | TokenType::Minus | TokenType::Star | TokenType::Slash | TokenType::Percent
+            | TokenType::Caret | TokenType::EqualEqual | TokenType::BangEqual | TokenType::Less
+            | TokenType::LessEqual | TokenType::Greater | TokenType::GreaterEqual | TokenType::And
+            | TokenType::Or
+        ));
+
+        depth > 0 || trailing
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::is_incomplete;
+
+        #[test]
+        fn open_brackets_and_strings_continue() {
+            assert!(is_incomplete("if x {\n"));
+            assert!(is_incomplete("f(1,\n"));
+            assert!(is_incomplete("s = \"\"\"\nhello\n"));
+            assert!(!is_incomplete("if x { print 1 }\n"));
+            assert!(!is_incomplete("s = \"\"\"\nhello\n\"\"\"\n"));
+        }
+
+        #[test]
+        fn trailing_binary_operators_continue() {
+            assert!(is_incomplete("x = 1 +\n"));
+            assert!(is_incomplete("a and\n"));
+            assert!(!is_incomplete("x = 1 + 2\n"));
+        }
+
+        // The parser doesn't allow a newline after these, so carrying on to
+        // the next line would always fail
+        #[test]
+        fn trailing_separators_do_not_continue() {
+            assert!(!is_incomplete("print 1,\n"));
+            assert!(!is_incomplete("x =\n"));
+            assert!(!is_incomplete("x ->\n"));
+            assert!(!is_incomplete("for i in 0..\n"));
+        }
+    }
+}
@@ -1,5 +1,6 @@
 pub mod token {
     use std::fmt;
+    use crate::error::error::Span;
 
     #[derive(Debug, Clone, PartialEq, Eq)]
     pub enum TokenType {
@@ -9,18 +10,57 @@ pub mod token {
         Star,
         Slash,
         Percent,
+        Caret,
+
+        // Comparison Operators
+        EqualEqual,
+        BangEqual,
+        Less,
+        LessEqual,
+        Greater,
+        GreaterEqual,
 
         // Symbols
         Equals,
         Bang,
+        Colon,
+        Arrow,
+        LBrace,
+        RBrace,
+        LParen,
+        RParen,
+        Comma,
+        DotDot,
 
         // Literals
+        // An interpolated string is lexed as it's `StringLit` segments with
+        // the tokens of each `{}` between `InterpStart` and `InterpEnd`
         StringLit,
+        InterpStart,
+        InterpEnd,
         NumberLit,
+        BoolLit,
         Type,
+        Ident,
 
         // Keywords
         Cout,
+        Proc,
+        Returns,
+        Return,
+        And,
+        Or,
+        Not,
+        If,
+        Else,
+        While,
+        For,
+        In,
+        Break,
+        Continue,
+
+        // Trivia, only kept if the lexer is asked to
+        Comment,
 
         // Other
         NewLn,
@@ -31,25 +71,28 @@ pub mod token {
     pub struct Token {
         pub token_type: TokenType,
         pub lexeme: String,
-        pub line: usize,
+        pub span: Span,
     }
 
     impl Token {
-        pub fn new(token_type: TokenType, lexeme: String, line: usize) -> Self {
-            Self { token_type, lexeme, line }
+        pub fn new(token_type: TokenType, lexeme: String, span: Span) -> Self {
+            Self { token_type, lexeme, span }
         }
 
-        pub fn end(line: usize) -> Self {
+        pub fn end(span: Span) -> Self {
             let token_type = TokenType::EndFile;
             let lexeme = String::from("<-- END OF FILE -->");
             
-            Self { token_type, lexeme, line } 
+            Self { token_type, lexeme, span } 
         }
     }
 
+    // One line per token, e.g. `3:5    Ident        "x"`
     impl fmt::Display for Token {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            write!(f, "{:?}\n\t{}: {}", self.token_type, self.line, self.lexeme)
+            let position = format!("{}:{}", self.span.line, self.span.col);
+            let token_type = format!("{:?}", self.token_type);
+            write!(f, "{:<8} {:<12} {:?}", position, token_type, self.lexeme)
         }
     }
 }
\ No newline at end of file